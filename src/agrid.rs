@@ -1,11 +1,14 @@
-use std::fmt::Debug;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Debug, Display, Formatter};
 
 use ndarray::{Array2, ArrayView1, Axis, Ix1};
 use ndarray::iter::Lanes;
 
-use crate::xy::{Dir, Piter, Point};
+use crate::util::fingerprint;
+use crate::xy::{Dir, Dir8, Piter, Point, Ray, Rect, Traversal};
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct AGrid {
     pub grid: Array2<char>,
 }
@@ -28,15 +31,25 @@ impl AGrid {
         GPoint { pos: Point { x, y }, chr: self.get_xy(x, y) }
     }
 
-    fn it(&self, start: Point<usize>, min_dir: Dir, min_len: usize, maj_dir: Dir, maj_len: usize) -> impl Iterator<Item=GPoint> + Debug + '_ {
+    fn it(&self, start: Point<usize>, min_dir: Dir, min_len: usize, maj_dir: Dir, maj_len: usize) -> impl DoubleEndedIterator<Item=GPoint> + ExactSizeIterator + Debug + '_ {
         Giter { grid: &self, it: Piter::new(start, min_dir, min_len, maj_dir, maj_len) }
     }
 
-    pub fn all_points(&self) -> impl Iterator<Item=GPoint> + Debug + '_ {
-        self.it(Point::origin(), Dir::Right, self.width(), Dir::Down, self.height())
+    pub fn all_points(&self) -> impl DoubleEndedIterator<Item=GPoint> + ExactSizeIterator + Debug + '_ {
+        Giter { grid: self, it: self.bounds().points() }
+    }
+
+    /// Returns all points in the grid in the given traversal order.
+    pub fn points_in(&self, order: Traversal) -> impl Iterator<Item=GPoint> + Debug + '_ {
+        Giter { grid: self, it: self.bounds().traverse(order) }
+    }
+
+    /// Returns the rectangle covering all points in the grid.
+    pub fn bounds(&self) -> Rect<usize> {
+        Rect::from_corners(Point::origin(), Point::new(self.width() - 1, self.height() - 1))
     }
 
-    pub fn points_from(&self, p: &Point<usize>, dirn: Dir) -> impl Iterator<Item=GPoint> + Debug + '_ {
+    pub fn points_from(&self, p: &Point<usize>, dirn: Dir) -> impl DoubleEndedIterator<Item=GPoint> + ExactSizeIterator + Debug + '_ {
         self.check_valid_point(p);
         match dirn {
             Dir::Right => self.it(*p, Dir::Right, self.width() - p.x, Dir::Up, 1),
@@ -46,10 +59,90 @@ impl AGrid {
         }
     }
 
-    pub fn points_after(&self, p: &Point<usize>, dirn: Dir) -> impl Iterator<Item=GPoint> + Debug + '_ {
+    pub fn points_after(&self, p: &Point<usize>, dirn: Dir) -> impl DoubleEndedIterator<Item=GPoint> + ExactSizeIterator + Debug + '_ {
         self.points_from(p, dirn).skip(1)
     }
 
+    /// Returns every cell on the edge of the grid, paired with the direction which faces into the
+    /// grid from that edge. Edges are visited clockwise from the top-left, and corner cells are
+    /// yielded once for each edge they are on (so the single cell of a 1x1 grid is yielded four
+    /// times). The border of an empty grid is empty.
+    pub fn border(&self) -> impl Iterator<Item=(GPoint, Dir)> + '_ {
+        let mut faces = vec![];
+        if self.width() > 0 && self.height() > 0 {
+            let (r, b) = (self.width() - 1, self.height() - 1);
+            // Each face runs along one edge, and is paired with the direction into the grid.
+            let face = |start: Point<usize>, along: Dir, inward: Dir| self.points_from(&start, along).map(move |p| (p, inward));
+            faces.push(face(Point::origin(), Dir::Right, Dir::Down));
+            faces.push(face(Point::new(r, 0), Dir::Down, Dir::Left));
+            faces.push(face(Point::new(r, b), Dir::Left, Dir::Up));
+            faces.push(face(Point::new(0, b), Dir::Up, Dir::Right));
+        }
+        faces.into_iter().flatten()
+    }
+
+    /// Returns the cells of the rectangular ring which is `distance` cells in from the edge of the
+    /// grid (so `ring(0)` is the outermost ring), visited clockwise from its top-left cell. Every
+    /// cell is yielded exactly once, and the ring is empty if it would lie beyond the center.
+    pub fn ring(&self, distance: usize) -> impl Iterator<Item=GPoint> + Debug + '_ {
+        let mut sides = vec![];
+        if 2 * distance < self.width() && 2 * distance < self.height() {
+            let (l, t) = (distance, distance);
+            let (r, b) = (self.width() - 1 - distance, self.height() - 1 - distance);
+            // Note that the minor axis must be horizontal, so vertical sides are one cell wide.
+            sides.push(self.it(Point::new(l, t), Dir::Right, r - l + 1, Dir::Down, 1));
+            if b > t {
+                sides.push(self.it(Point::new(r, t + 1), Dir::Right, 1, Dir::Down, b - t));
+                if r > l {
+                    sides.push(self.it(Point::new(r - 1, b), Dir::Left, r - l, Dir::Up, 1));
+                    if b > t + 1 {
+                        sides.push(self.it(Point::new(l, b - 1), Dir::Right, 1, Dir::Up, b - t - 1));
+                    }
+                }
+            }
+        }
+        sides.into_iter().flatten()
+    }
+
+    /// Returns the points from the given point (inclusive) to the edge of the grid, in any of the
+    /// eight compass directions.
+    pub fn ray_from(&self, p: &Point<usize>, dirn: Dir8) -> impl Iterator<Item=GPoint> + Debug + '_ {
+        self.check_valid_point(p);
+        let (h, v) = dirn.components();
+        let x_len = match h {
+            Some(Dir::Right) => self.width() - p.x,
+            Some(_) => p.x + 1,
+            None => usize::MAX,
+        };
+        let y_len = match v {
+            Some(Dir::Down) => self.height() - p.y,
+            Some(_) => p.y + 1,
+            None => usize::MAX,
+        };
+        Giter { grid: self, it: Ray::new(*p, dirn, x_len.min(y_len)) }
+    }
+
+    /// Returns the "leading" diagonals of the grid (running top-left to bottom-right), in order
+    /// from the top-right corner to the bottom-left corner.
+    pub fn diagonals(&self) -> impl Iterator<Item=impl Iterator<Item=GPoint> + Debug + '_> + '_ {
+        let (w, h) = (self.width(), self.height());
+        (0..w + h - 1).map(move |k| {
+            let start = if k < w { Point::new(w - 1 - k, 0) } else { Point::new(0, k + 1 - w) };
+            self.ray_from(&start, Dir8::SouthEast)
+        })
+    }
+
+    /// Returns the anti-diagonals of the grid (running top-right to bottom-left), in order from the
+    /// top-left corner to the bottom-right corner. All points in the `k`th anti-diagonal satisfy
+    /// `x + y == k`.
+    pub fn anti_diagonals(&self) -> impl Iterator<Item=impl Iterator<Item=GPoint> + Debug + '_> + '_ {
+        let (w, h) = (self.width(), self.height());
+        (0..w + h - 1).map(move |k| {
+            let start = if k < w { Point::new(k, 0) } else { Point::new(w - 1, k + 1 - w) };
+            self.ray_from(&start, Dir8::SouthWest)
+        })
+    }
+
     fn check_valid_point(&self, p: &Point<usize>) {
         assert!(p.x < self.width() && p.y < self.height());
     }
@@ -78,35 +171,105 @@ impl AGrid {
         self.grid.columns()
     }
 
+    /// Returns a stable 64-bit fingerprint of the grid's dimensions and contents, suitable for
+    /// quickly detecting repeated states. Use equality to confirm any match.
+    pub fn fingerprint(&self) -> u64 {
+        fingerprint(self)
+    }
+
+    /// Parses a grid from newline separated lines, panicking if the lines are not a valid grid.
+    /// Trailing blank lines are ignored.
     pub fn from_lines(s: &str) -> AGrid {
         s.split('\n').collect()
     }
+
+    /// Parses a grid from newline separated lines according to the given options. Trailing blank
+    /// lines (including the empty line after a final newline) are ignored.
+    pub fn parse(s: &str, opts: &ParseOptions) -> Result<AGrid, GridError> {
+        AGrid::try_from_lines(s.split('\n'), opts)
+    }
+
+    /// Creates a grid from a sequence of lines according to the given options. Trailing blank
+    /// lines are ignored.
+    pub fn try_from_lines<'a, I: IntoIterator<Item=&'a str>>(lines: I, opts: &ParseOptions) -> Result<AGrid, GridError> {
+        let mut rows: Vec<Vec<char>> = lines.into_iter().map(|s| s.chars().collect()).collect();
+        while rows.last().is_some_and(|r| r.is_empty()) {
+            rows.pop();
+        }
+        if opts.ascii_only {
+            for (y, row) in rows.iter().enumerate() {
+                if let Some(x) = row.iter().position(|c| !c.is_ascii()) {
+                    return Err(GridError::NonAscii { pos: Point::new(x, y), chr: row[x] });
+                }
+            }
+        }
+        // Only trailing blank lines are removed, so any remaining rows include a non-empty line.
+        if rows.is_empty() {
+            return Err(GridError::Empty);
+        }
+        let width = match opts.fill {
+            Some(_) => rows.iter().map(|r| r.len()).max().unwrap(),
+            None => rows[0].len(),
+        };
+        let height = rows.len();
+        let mut chars: Vec<char> = Vec::with_capacity(width * height);
+        for (y, mut row) in rows.into_iter().enumerate() {
+            if row.len() != width {
+                match opts.fill {
+                    Some(c) => row.resize(width, c),
+                    None => return Err(GridError::InconsistentWidth { line: y, width: row.len(), expected: width }),
+                }
+            }
+            chars.extend(row);
+        }
+        // Axis 0 is height, since that's the outermost dimension to stride over.
+        let grid = Array2::from_shape_vec((height, width), chars).unwrap();
+        Ok(AGrid { grid })
+    }
 }
 
 impl<'a> FromIterator<&'a str> for AGrid {
     fn from_iter<I: IntoIterator<Item=&'a str>>(iter: I) -> Self {
-        let mut ascii: Vec<char> = vec![];
-        let mut width: usize = 0;
-        let mut height: usize = 0;
-        for s in iter {
-            let before_len = ascii.len();
-            ascii.extend(s.chars().map(|c| {
-                assert!(c.is_ascii());
-                c
-            }));
-            let w = ascii.len() - before_len;
-            if width == 0 { width = w; } else {
-                assert_eq!(w, width, "Inconsistent width");
-            }
-            height += 1;
+        AGrid::try_from_lines(iter, &ParseOptions::default()).unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+/// Options for parsing an `AGrid` from text.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ParseOptions {
+    /// If set, lines shorter than the longest line are padded with this character, rather than
+    /// being rejected.
+    pub fill: Option<char>,
+    /// If set, any non-ASCII character is rejected. By default any Unicode character (e.g. box
+    /// drawing characters) is accepted.
+    pub ascii_only: bool,
+}
+
+/// The reasons why text cannot be parsed as an `AGrid`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GridError {
+    /// There were no non-empty lines.
+    Empty,
+    /// A line (zero indexed) was not the same width as the first line.
+    InconsistentWidth { line: usize, width: usize, expected: usize },
+    /// A non-ASCII character was found when only ASCII was permitted.
+    NonAscii { pos: Point<usize>, chr: char },
+}
+
+impl Display for GridError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            GridError::Empty => write!(f, "Grid has no non-empty lines"),
+            GridError::InconsistentWidth { line, width, expected } =>
+                write!(f, "Inconsistent width at line {}: {} (expected {})", line, width, expected),
+            GridError::NonAscii { pos, chr } =>
+                write!(f, "Non-ASCII character {:?} at ({}, {})", chr, pos.x, pos.y),
         }
-        // Axis 0 is height, since that's the outermost dimension to stride over.
-        assert!(width > 0 && height > 0);
-        let grid = Array2::from_shape_vec((height, width), ascii).unwrap();
-        AGrid { grid }
     }
 }
 
+impl Error for GridError {}
+
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct GPoint {
     pub pos: Point<usize>,
@@ -120,25 +283,37 @@ impl From<(Point<usize>, char)> for GPoint {
 }
 
 #[derive(Debug)]
-struct Giter<'a> {
+struct Giter<'a, It: Iterator<Item=Point<usize>>> {
     grid: &'a AGrid,
-    it: Piter<usize>,
+    it: It,
 }
 
-impl<'a> Iterator for Giter<'a> {
+impl<'a, It: Iterator<Item=Point<usize>>> Iterator for Giter<'a, It> {
     type Item = GPoint;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.it.next().map(move |p| GPoint { pos: p, chr: self.grid.get(&p) })
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.it.size_hint()
+    }
 }
 
+impl<'a, It: DoubleEndedIterator<Item=Point<usize>>> DoubleEndedIterator for Giter<'a, It> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.it.next_back().map(move |p| GPoint { pos: p, chr: self.grid.get(&p) })
+    }
+}
+
+impl<'a, It: ExactSizeIterator<Item=Point<usize>>> ExactSizeIterator for Giter<'a, It> {}
+
 #[cfg(test)]
 mod tests {
     use indoc::indoc;
     use ndarray::array;
 
-    use Dir::Right;
+    use Dir::{Down, Left, Right, Up};
 
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
@@ -164,4 +339,119 @@ mod tests {
 
         assert_eq!(g.points_from(&Point::new(5, 2), Right).map(|p| p.chr).collect::<Vec<_>>(), vec!['r', 'q', 'p', 'o', 'n', 'm']);
     }
+
+    #[test]
+    fn test_border() {
+        let g = AGrid::from_lines("abc\ndef");
+        assert_eq!(g.border().map(|(p, d)| (p.chr, d)).collect::<Vec<_>>(), vec![
+            ('a', Down), ('b', Down), ('c', Down),
+            ('c', Left), ('f', Left),
+            ('f', Up), ('e', Up), ('d', Up),
+            ('d', Right), ('a', Right)]);
+
+        // Corners and opposite faces coincide, but each face still yields all of its cells.
+        let g = AGrid::from_lines("a");
+        assert_eq!(g.border().map(|(p, d)| (p.chr, d)).collect::<Vec<_>>(), vec![
+            ('a', Down), ('a', Left), ('a', Up), ('a', Right)]);
+        let g = AGrid::from_lines("ab");
+        assert_eq!(g.border().map(|(p, d)| (p.chr, d)).collect::<Vec<_>>(), vec![
+            ('a', Down), ('b', Down),
+            ('b', Left),
+            ('b', Up), ('a', Up),
+            ('a', Right)]);
+        let g = AGrid::from_lines("a
+b");
+        assert_eq!(g.border().map(|(p, d)| (p.chr, d)).collect::<Vec<_>>(), vec![
+            ('a', Down),
+            ('a', Left), ('b', Left),
+            ('b', Up),
+            ('b', Right), ('a', Right)]);
+
+        let empty = AGrid { grid: Array2::from_elem((0, 3), '.') };
+        assert_eq!(empty.border().count(), 0);
+        let empty = AGrid { grid: Array2::from_elem((3, 0), '.') };
+        assert_eq!(empty.border().count(), 0);
+    }
+
+    #[test]
+    fn test_ring() {
+        let s = indoc! {"abcde
+           fghij
+           klmno
+           pqrst"};
+        let g = AGrid::from_lines(s);
+        let ring = |n| g.ring(n).map(|p| p.chr).collect::<String>();
+        assert_eq!(ring(0), "abcdejotsrqpkf");
+        assert_eq!(ring(1), "ghinml");
+        assert_eq!(ring(2), "");
+        // Degenerate rings (a single row or column) still visit each cell once.
+        assert_eq!(AGrid::from_lines("abc").ring(0).map(|p| p.chr).collect::<String>(), "abc");
+        assert_eq!(AGrid::from_lines("a\nb\nc").ring(0).map(|p| p.chr).collect::<String>(), "abc");
+    }
+
+    #[test]
+    fn test_parse() {
+        // A trailing newline no longer produces an empty row.
+        let g = AGrid::from_lines("ab\ncd\n\n");
+        assert_eq!((g.width(), g.height()), (2, 2));
+
+        let g = AGrid::from_lines("┌─┐\n└─┘");
+        assert_eq!(g.get_xy(1, 1), '─');
+
+        let opts = ParseOptions { fill: Some('.'), ..Default::default() };
+        let g = AGrid::parse("a\nbcd\n\nef", &opts).unwrap();
+        assert_eq!((g.width(), g.height()), (3, 4));
+        assert_eq!(g.row(0), array!['a', '.', '.']);
+        assert_eq!(g.row(2), array!['.', '.', '.']);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let default = ParseOptions::default();
+        assert_eq!(AGrid::parse("\n\n", &default), Err(GridError::Empty));
+        assert_eq!(AGrid::parse("abc\nde", &default),
+                   Err(GridError::InconsistentWidth { line: 1, width: 2, expected: 3 }));
+        // Leading blank lines are not ignored, so are inconsistent with the following lines.
+        assert_eq!(AGrid::parse("\nabc", &default),
+                   Err(GridError::InconsistentWidth { line: 1, width: 3, expected: 0 }));
+        let filled = ParseOptions { fill: Some('.'), ..Default::default() };
+        assert_eq!(AGrid::parse("\nabc", &filled).unwrap(), AGrid::from_lines("...\nabc"));
+        let ascii = ParseOptions { ascii_only: true, ..Default::default() };
+        assert_eq!(AGrid::parse("ab\ncé", &ascii),
+                   Err(GridError::NonAscii { pos: Point::new(1, 1), chr: 'é' }));
+        assert_eq!(AGrid::parse("abc\nde", &default).unwrap_err().to_string(),
+                   "Inconsistent width at line 1: 2 (expected 3)");
+    }
+
+    #[test]
+    fn test_diagonals() {
+        let s = indoc! {"abc
+           def"};
+        let g = AGrid::from_lines(s);
+        let chars = |it: &mut dyn Iterator<Item=GPoint>| it.map(|p| p.chr).collect::<String>();
+        assert_eq!(chars(&mut g.ray_from(&Point::new(0, 0), Dir8::SouthEast)), "ae");
+        assert_eq!(chars(&mut g.ray_from(&Point::new(2, 1), Dir8::North)), "fc");
+        assert_eq!(g.diagonals().map(|mut d| chars(&mut d)).collect::<Vec<_>>(), vec!["c", "bf", "ae", "d"]);
+        assert_eq!(g.anti_diagonals().map(|mut d| chars(&mut d)).collect::<Vec<_>>(), vec!["a", "bd", "ce", "f"]);
+    }
+
+    #[test]
+    fn test_points_in() {
+        let s = indoc! {"abc
+           def"};
+        let g = AGrid::from_lines(s);
+        let chars = |t| g.points_in(t).map(|p| p.chr).collect::<String>();
+        assert_eq!(chars(Traversal::ColumnMajor), "adbecf");
+        assert_eq!(chars(Traversal::Snake), "abcfed");
+        assert_eq!(chars(Traversal::Spiral), "abcfed");
+        assert_eq!(chars(Traversal::Diagonal), "abdcef");
+    }
+
+    #[test]
+    fn test_iter_rev() {
+        let g = AGrid::from_lines("abc\ndef");
+        assert_eq!(g.points_after(&Point::new(0, 1), Right).rev().map(|p| p.chr).collect::<String>(), "fe");
+        assert_eq!(g.points_from(&Point::new(2, 1), Up).len(), 2);
+        assert_eq!(g.all_points().rev().map(|p| p.chr).collect::<String>(), "fedcba");
+    }
 }
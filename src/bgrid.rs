@@ -0,0 +1,191 @@
+use std::ops::BitXor;
+
+use crate::agrid::AGrid;
+use crate::xy::Point;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// A fixed length sequence of bits, packed into 64-bit words.
+///
+/// Any unused bits in the final word are always zero, so equality and hashing of `Bits` is just
+/// that of the underlying words.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Bits {
+    words: Vec<u64>,
+    len: usize,
+}
+
+#[allow(unused)]
+impl Bits {
+    /// Creates a sequence of `len` bits, all initially zero.
+    pub fn new(len: usize) -> Bits {
+        Bits { words: vec![0; len.div_ceil(WORD_BITS)], len }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        assert!(i < self.len, "Bit index out of range: {} >= {}", i, self.len);
+        (self.words[i / WORD_BITS] >> (i % WORD_BITS)) & 1 != 0
+    }
+
+    pub fn set(&mut self, i: usize, value: bool) {
+        assert!(i < self.len, "Bit index out of range: {} >= {}", i, self.len);
+        let mask = 1u64 << (i % WORD_BITS);
+        if value {
+            self.words[i / WORD_BITS] |= mask;
+        } else {
+            self.words[i / WORD_BITS] &= !mask;
+        }
+    }
+
+    /// Returns the number of set bits.
+    pub fn count_ones(&self) -> u32 {
+        self.words.iter().map(|w| w.count_ones()).sum()
+    }
+
+    /// Returns the number of positions at which this sequence differs from another of the same
+    /// length (i.e. the Hamming distance), without allocating an intermediate result.
+    pub fn diff_count(&self, other: &Bits) -> u32 {
+        assert_eq!(self.len, other.len, "Mismatched lengths");
+        self.words.iter().zip(other.words.iter()).map(|(a, b)| (a ^ b).count_ones()).sum()
+    }
+}
+
+impl BitXor for &Bits {
+    type Output = Bits;
+
+    fn bitxor(self, rhs: &Bits) -> Bits {
+        assert_eq!(self.len, rhs.len, "Mismatched lengths");
+        let words = self.words.iter().zip(rhs.words.iter()).map(|(a, b)| a ^ b).collect();
+        Bits { words, len: self.len }
+    }
+}
+
+/// A bit-packed view of a grid, holding each row and each column as a `Bits` instance so that
+/// whole rows or columns can be compared in a few word operations, regardless of grid size.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct BGrid {
+    rows: Vec<Bits>,
+    cols: Vec<Bits>,
+}
+
+#[allow(unused)]
+impl BGrid {
+    /// Creates an empty grid of the given size, with all bits zero.
+    pub fn new(width: usize, height: usize) -> BGrid {
+        BGrid { rows: vec![Bits::new(width); height], cols: vec![Bits::new(height); width] }
+    }
+
+    /// Creates a grid with bits set for every point of the given `AGrid` whose character matches
+    /// the predicate.
+    pub fn from_grid<F: Fn(char) -> bool>(grid: &AGrid, pred: F) -> BGrid {
+        let mut bgrid = BGrid::new(grid.width(), grid.height());
+        for p in grid.all_points().filter(|p| pred(p.chr)) {
+            bgrid.set(&p.pos, true);
+        }
+        bgrid
+    }
+
+    pub fn get(&self, p: &Point<usize>) -> bool {
+        self.get_xy(p.x, p.y)
+    }
+
+    pub fn get_xy(&self, x: usize, y: usize) -> bool {
+        self.rows[y].get(x)
+    }
+
+    pub fn set(&mut self, p: &Point<usize>, value: bool) {
+        self.set_xy(p.x, p.y, value)
+    }
+
+    pub fn set_xy(&mut self, x: usize, y: usize, value: bool) {
+        self.rows[y].set(x, value);
+        self.cols[x].set(y, value);
+    }
+
+    pub fn width(&self) -> usize {
+        self.cols.len()
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn row(&self, y: usize) -> &Bits {
+        &self.rows[y]
+    }
+
+    pub fn rows(&self) -> &[Bits] {
+        &self.rows
+    }
+
+    pub fn col(&self, x: usize) -> &Bits {
+        &self.cols[x]
+    }
+
+    pub fn cols(&self) -> &[Bits] {
+        &self.cols
+    }
+
+    /// Returns the total number of set bits in the grid.
+    pub fn count_ones(&self) -> u32 {
+        self.rows.iter().map(|r| r.count_ones()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    #[test]
+    fn test_bits() {
+        let mut a = Bits::new(130);
+        let mut b = Bits::new(130);
+        a.set(0, true);
+        a.set(64, true);
+        a.set(129, true);
+        b.set(129, true);
+        assert_eq!(a.count_ones(), 3);
+        assert_eq!(a.diff_count(&b), 2);
+        assert_eq!((&a ^ &b).count_ones(), 2);
+        assert_ne!(a, b);
+        a.set(0, false);
+        a.set(64, false);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_grid() {
+        let s = indoc! {"#.#
+           ...
+           .##
+           #.."};
+        let g = BGrid::from_grid(&AGrid::from_lines(s), |c| c == '#');
+        assert_eq!((g.width(), g.height()), (3, 4));
+        assert_eq!(g.count_ones(), 5);
+        assert!(g.get_xy(2, 2));
+        assert!(!g.get_xy(1, 1));
+        assert_eq!(g.row(0).diff_count(g.row(1)), 2);
+        assert_eq!(g.col(1).count_ones(), 1);
+    }
+
+    #[test]
+    fn test_wide_grid() {
+        // Wider than a single word, which the old u32-per-row representation could not hold.
+        let line = ".".repeat(99) + "#";
+        let g = BGrid::from_grid(&AGrid::from_lines(&format!("{}\n{}", line, line)), |c| c == '#');
+        assert!(g.get_xy(99, 1));
+        assert_eq!(g.row(0), g.row(1));
+        assert_eq!(g.col(99).count_ones(), 2);
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// The shape of a sequence `x0, f(x0), f(f(x0)), ...` which eventually repeats.
+///
+/// The first `prefix_len` states are never revisited, after which the sequence repeats with a
+/// period of `cycle_len`. The state at index `prefix_len` is the first state of the cycle.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Period {
+    pub prefix_len: usize,
+    pub cycle_len: usize,
+}
+
+#[allow(unused)]
+impl Period {
+    /// Maps any iteration index onto the smallest index with the same state (i.e. an index less
+    /// than `prefix_len + cycle_len`).
+    pub fn reduce(&self, n: u64) -> usize {
+        let prefix_len = self.prefix_len as u64;
+        if n < prefix_len { n as usize } else { (prefix_len + (n - prefix_len) % self.cycle_len as u64) as usize }
+    }
+
+    /// Returns the state at iteration `n` by stepping from the start state. At most
+    /// `prefix_len + cycle_len - 1` steps are taken, regardless of `n`.
+    pub fn state_at<S, F: Fn(&S) -> S>(&self, start: S, step: F, n: u64) -> S {
+        (0..self.reduce(n)).fold(start, |s, _| step(&s))
+    }
+}
+
+/// Finds the period of a sequence using Floyd's "tortoise and hare" algorithm.
+///
+/// This uses constant memory, but evaluates the step function roughly three times per element
+/// of the sequence. Prefer `brent()`, which does less work, unless you have a reason not to.
+#[allow(unused)]
+pub fn floyd<S: Eq, F: Fn(&S) -> S>(start: S, step: F) -> Period {
+    // Find some multiple of the cycle length (where the hare is "lapping" the tortoise).
+    let mut tortoise = step(&start);
+    let mut hare = step(&tortoise);
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&step(&hare));
+    }
+    // The distance from the start to the cycle is the same as from the meeting point to the cycle.
+    let mut prefix_len = 0;
+    tortoise = start;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        prefix_len += 1;
+    }
+    // Walk once around the cycle to measure its length.
+    let mut cycle_len = 1;
+    hare = step(&tortoise);
+    while tortoise != hare {
+        hare = step(&hare);
+        cycle_len += 1;
+    }
+    Period { prefix_len, cycle_len }
+}
+
+/// Finds the period of a sequence using Brent's algorithm.
+///
+/// This uses constant memory, but requires the start state to be cloneable since the sequence is
+/// walked from the start twice.
+#[allow(unused)]
+pub fn brent<S: Eq + Clone, F: Fn(&S) -> S>(start: S, step: F) -> Period {
+    // Find the cycle length by teleporting the tortoise to the hare at every power of two.
+    let mut power = 1;
+    let mut cycle_len = 1;
+    let mut tortoise = start.clone();
+    let mut hare = step(&start);
+    while tortoise != hare {
+        if power == cycle_len {
+            tortoise = hare.clone();
+            power *= 2;
+            cycle_len = 0;
+        }
+        hare = step(&hare);
+        cycle_len += 1;
+    }
+    // With the hare a full cycle ahead of the tortoise, they meet at the start of the cycle.
+    let mut tortoise = start.clone();
+    let mut hare = (0..cycle_len).fold(start, |s, _| step(&s));
+    let mut prefix_len = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        prefix_len += 1;
+    }
+    Period { prefix_len, cycle_len }
+}
+
+/// The states of a repeating sequence, up to and including one full cycle.
+#[derive(Clone, Debug)]
+pub struct History<S> {
+    period: Period,
+    states: Vec<S>,
+}
+
+#[allow(unused)]
+impl<S> History<S> {
+    pub fn period(&self) -> Period { self.period }
+
+    /// Returns the distinct states of the sequence in order (i.e. the prefix and one cycle).
+    pub fn states(&self) -> &[S] { &self.states }
+
+    /// Returns the state at any iteration `n` without further evaluation of the step function.
+    pub fn get(&self, n: u64) -> &S {
+        &self.states[self.period.reduce(n)]
+    }
+}
+
+/// Finds the period of a sequence by recording every state in a hash map until one repeats.
+///
+/// This evaluates the step function exactly once per distinct state, and retains all states so
+/// any iteration can be looked up afterwards. For large states consider wrapping them in
+/// `util::Fingerprinted` to avoid rehashing.
+#[allow(unused)]
+pub fn hashed<S: Eq + Hash + Clone, F: Fn(&S) -> S>(start: S, step: F) -> History<S> {
+    let mut seen: HashMap<S, usize> = HashMap::new();
+    let mut states: Vec<S> = vec![];
+    let mut cur = start;
+    loop {
+        if let Some(&prefix_len) = seen.get(&cur) {
+            let period = Period { prefix_len, cycle_len: states.len() - prefix_len };
+            return History { period, states };
+        }
+        seen.insert(cur.clone(), states.len());
+        let next = step(&cur);
+        states.push(cur);
+        cur = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    // 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 3, 4, 5, ...
+    fn step(n: &u32) -> u32 {
+        if *n == 9 { 3 } else { n + 1 }
+    }
+
+    #[test]
+    fn test_period() {
+        let expected = Period { prefix_len: 3, cycle_len: 7 };
+        assert_eq!(floyd(0, step), expected);
+        assert_eq!(brent(0, step), expected);
+        assert_eq!(hashed(0, step).period(), expected);
+
+        // Starting inside the cycle has no prefix.
+        assert_eq!(brent(5, step), Period { prefix_len: 0, cycle_len: 7 });
+    }
+
+    #[test]
+    fn test_state_at() {
+        let history = hashed(0, step);
+        assert_eq!(history.states().len(), 10);
+        assert_eq!(*history.get(2), 2);
+        assert_eq!(*history.get(10), 3);
+        assert_eq!(*history.get(1_000_000_000), 3 + (1_000_000_000 - 3) % 7);
+        assert_eq!(history.period().state_at(0, step, 1_000_000_000), *history.get(1_000_000_000));
+    }
+}
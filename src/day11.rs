@@ -18,7 +18,7 @@ fn sum_dist(galaxies: &HashSet<Point<usize>>, factor: usize) -> i64 {
     galaxies.iter()
         .map(|p| Point::new(*xmap.get(&p.x).unwrap(), *ymap.get(&p.y).unwrap()))
         .tuple_combinations()
-        .map(|(a, b)| (a.x - b.x).abs() + (a.y - b.y).abs())
+        .map(|(a, b)| a.manhattan(&b))
         .sum()
 }
 
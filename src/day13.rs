@@ -1,33 +1,32 @@
-use std::fmt::Debug;
-
 use itertools::Itertools;
 
 use crate::agrid::AGrid;
+use crate::bgrid::{BGrid, Bits};
 
 // https://adventofcode.com/2023/day/13
 pub fn run(input: &str) -> (usize, usize) {
     let mut part1 = 0;
     let mut part2 = 0;
-    for img in input.split("\n\n").map(Img::new) {
-        part1 += flip_offset(&img.cols) + (100 * flip_offset(&img.rows));
-        part2 += corrected_flip_offset(&img.cols) + (100 * corrected_flip_offset(&img.rows));
+    for img in input.split("\n\n").map(|s| BGrid::from_grid(&AGrid::from_lines(s), |c| c == '#')) {
+        part1 += flip_offset(img.cols()) + (100 * flip_offset(img.rows()));
+        part2 += corrected_flip_offset(img.cols()) + (100 * corrected_flip_offset(img.rows()));
     }
     (part1, part2)
 }
 
-fn flip_offset(arr: &[u32]) -> usize {
+fn flip_offset(arr: &[Bits]) -> usize {
     // Without accounting for correction, the flip index is just the unique index for which
     // is_flip() returns true (note that the flip index is never 0 since it's "to the right
     // of the mirror").
     (1..arr.len()).filter(|&i| is_flip(arr, i, None)).at_most_one().unwrap().unwrap_or(0)
 }
 
-fn corrected_flip_offset(arr: &[u32]) -> usize {
+fn corrected_flip_offset(arr: &[Bits]) -> usize {
     // Map all pairs of (even x odd) indices, since mirrored elements always have opposite parity.
     (0..arr.len()).step_by(2)
         .flat_map(|i| (1..arr.len()).step_by(2).map(move |j| (i, j)))
         // Keep only those index pairs whose values differ by exactly one bit.
-        .filter(|&(a, b)| one_bit_diff(arr[a], arr[b]))
+        .filter(|&(a, b)| arr[a].diff_count(&arr[b]) == 1)
         // Map to the mid-point "flip index" and positive offset to corrected values.
         .map(to_index_and_offset)
         // Ensure that the correction is valid (many are not).
@@ -40,7 +39,7 @@ fn corrected_flip_offset(arr: &[u32]) -> usize {
 
 //  [0...idx-1] <-> [idx...arr.len()-1]
 //  |   idx   |  +  |  arr.len()-idx  | == arr.len()
-fn is_flip(arr: &[u32], flip_idx: usize, correction_offset: Option<usize>) -> bool {
+fn is_flip(arr: &[Bits], flip_idx: usize, correction_offset: Option<usize>) -> bool {
     // Shortest number of elements to either edge forms the mirrored region.
     let len = flip_idx.min(arr.len() - flip_idx);
     // If we were given a correction offset, it must be in the mirrored region.
@@ -53,11 +52,6 @@ fn is_flip(arr: &[u32], flip_idx: usize, correction_offset: Option<usize>) -> bo
         .all(|i| arr[flip_idx - (i + 1)] == arr[flip_idx + i])
 }
 
-fn one_bit_diff(a: u32, b: u32) -> bool {
-    let xor = a ^ b;
-    xor != 0 && (xor & (xor - 1)) == 0
-}
-
 // Convert a pair of even/odd error indices where a correction can occur,
 // to the assumed flip index and relative correction offset.
 fn to_index_and_offset(err: (usize, usize)) -> (usize, usize) {
@@ -67,24 +61,3 @@ fn to_index_and_offset(err: (usize, usize)) -> (usize, usize) {
     let offset = err.0.max(err.1) - flip_idx;
     (flip_idx, offset)
 }
-
-#[derive(Debug)]
-struct Img {
-    rows: Vec<u32>,
-    cols: Vec<u32>,
-}
-
-impl Img {
-    fn new(s: &str) -> Img {
-        let grid = AGrid::from_lines(s);
-        let mut cols: Vec<u32> = [0u32].repeat(grid.width());
-        let mut rows: Vec<u32> = [0u32].repeat(grid.height());
-        for p in grid.all_points() {
-            if p.chr == '#' {
-                cols[p.pos.x] |= 1 << p.pos.y;
-                rows[p.pos.y] |= 1 << p.pos.x;
-            }
-        }
-        Img { rows, cols }
-    }
-}
\ No newline at end of file
@@ -4,7 +4,9 @@
 use std::collections::HashMap;
 
 use crate::agrid::AGrid;
+use crate::cycle;
 use crate::day14::CardinalFrame::{East, North, South, West};
+use crate::util::Fingerprinted;
 use crate::xy::Point;
 
 // A map of non-rolling rock positions according to a specific direction ID.
@@ -43,24 +45,15 @@ fn score(points: &[Point<u32>]) -> u32 {
 }
 
 fn get_billionth_score(cycle: &Cycle, mut points: Vec<Point<u32>>) -> u32 {
-    // Using the score to determine an exact match of cycles is dubious, but happens to work.
-    // Really that's just the hash value and you should have the vector stored to test against.
-    let mut cycles_state: HashMap<u32, u32> = HashMap::new();
-    let mut cycle_count = 0u32;
-    let mut end_cycle = None;
-    loop {
-        cycle_count += 1;
-        points = cycle.spin(&points);
-        if cycle_count > 50 && end_cycle.is_none() {
-            if let Some(seen_before) = cycles_state.insert(score(&points), cycle_count) {
-                let cycle_length = cycle_count - seen_before;
-                end_cycle = Some(cycle_count + (1_000_000_000 - cycle_count) % cycle_length);
-            }
-        }
-        if end_cycle.is_some_and(|end| cycle_count == end) {
-            return score(&points);
-        }
-    }
+    // Key on the full rock state (sorted, since spin order is arbitrary) so a repeated state is an
+    // exact match, while the fingerprint keeps hashing cheap.
+    points.sort_unstable();
+    let history = cycle::hashed(Fingerprinted::new(points), |state| {
+        let mut next = cycle.spin(state.value());
+        next.sort_unstable();
+        Fingerprinted::new(next)
+    });
+    score(history.get(1_000_000_000).value())
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
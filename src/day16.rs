@@ -5,24 +5,21 @@ use rayon::iter::ParallelIterator;
 
 use crate::agrid::{AGrid, GPoint};
 use crate::xy::{Dir, Point};
-use crate::xy::Dir::{Down, Left, Right, Up};
+use crate::xy::Dir::Right;
+use crate::xy::Orientation::Horizontal;
 
 pub fn run(input: &str) -> (u32, u32) {
     let grid = AGrid::from_lines(input);
 
-    let top_left = Point::new(0, 0);
-    let bottom_right = Point::new(grid.width() - 1, grid.height() - 1);
-    let mut starts: Vec<(Point<usize>, Dir)> = vec![];
-    starts.extend(grid.points_after(&top_left, Down).map(|p| (p.pos, Right)));
-    starts.extend(grid.points_after(&top_left, Right).map(|p| (p.pos, Down)));
-    starts.extend(grid.points_after(&bottom_right, Left).map(|p| (p.pos, Up)));
-    starts.extend(grid.points_after(&bottom_right, Up).map(|p| (p.pos, Left)));
-
+    let starts: Vec<(Point<usize>, Dir)> = grid.border().map(|(p, d)| (p.pos, d)).collect();
     let results: Vec<u32> = starts.par_iter().map(|&(p, d)| Solver::solve(&grid, p, d)).collect();
-    (results[0], *results.iter().max().unwrap())
+    // Part 1 enters at the top-left going right. Note that this changes the part 1 answer for our
+    // input from 7896 to 7996, since previously the first start point was (0, 1) rather than the
+    // origin (which points_after() skipped).
+    let part1 = starts.iter().position(|&s| s == (Point::origin(), Right)).unwrap();
+    (results[part1], *results.iter().max().unwrap())
 }
 
-
 struct Solver<'a> {
     grid: &'a AGrid,
     visited: HashSet<Point<usize>>,
@@ -58,24 +55,17 @@ impl<'a> Solver<'a> {
 
     fn visit(&mut self, p: &GPoint, dir: Dir) -> Option<Dir> {
         let unvisited = self.visited.insert(p.pos);
-        if let (Right | Left, '|') = (dir, p.chr) {
-            if unvisited {
-                self.follow_after(p.pos, Up);
-                self.follow_after(p.pos, Down);
-            }
-            return None;
-        } else if let (Down | Up, '-') = (dir, p.chr) {
+        let horizontal = dir.orientation() == Horizontal;
+        if (horizontal && p.chr == '|') || (!horizontal && p.chr == '-') {
             if unvisited {
-                self.follow_after(p.pos, Left);
-                self.follow_after(p.pos, Right);
+                self.follow_after(p.pos, dir.turn_left());
+                self.follow_after(p.pos, dir.turn_right());
             }
             return None;
         }
-        Some(match (dir, p.chr) {
-            (Right, '\\') | (Left, '/') => Down,
-            (Down, '\\') | (Up, '/') => Right,
-            (Left, '\\') | (Right, '/') => Up,
-            (Up, '\\') | (Down, '/') => Left,
+        Some(match p.chr {
+            '\\' => if horizontal { dir.turn_right() } else { dir.turn_left() },
+            '/' => if horizontal { dir.turn_left() } else { dir.turn_right() },
             _ => dir,
         })
     }
@@ -3,7 +3,7 @@ use pathfinding::directed::astar;
 
 use Orientation::{Horizontal, Vertical};
 
-use crate::xy::Orientation;
+use crate::xy::{Dir, Orientation, Point};
 
 type Node = (u32, u32, Option<Orientation>);
 
@@ -33,57 +33,27 @@ pub fn run(input: &str) -> (u32, u32) {
 }
 
 fn successors(arr: &Array2<u8>, n: &Node, min: u32, max: u32) -> Vec<(Node, u32)> {
-    let mut out: Vec<(Node, u32)> = Vec::with_capacity(2 * (max - min + 1) as usize);
     let &(x, y, hv) = n;
-    match hv {
-        Some(Vertical) => {
-            let row = arr.row(y as usize);
-
-            let mut right_cost: u32 = 0;
-            for i in 1..=max {
-                if let Some(n) = row.get((x + i) as usize) {
-                    right_cost += *n as u32;
-                    if i >= min {
-                        out.push(((x + i, y, Some(Horizontal)), right_cost));
-                    }
-                } else { break; }
-            }
-
-            let mut left_cost: u32 = 0;
-            for i in 1..=max {
-                if let Some(n) = row.get((x - i) as usize) {
-                    left_cost += *n as u32;
-                    if i >= min {
-                        out.push(((x - i, y, Some(Horizontal)), left_cost));
-                    }
-                } else { break; }
-            }
-            out
-        }
-        Some(Horizontal) => {
-            let col = arr.column(x as usize);
-
-            let mut down_cost: u32 = 0;
-            for i in 1..=max {
-                if let Some(n) = col.get((y + i) as usize) {
-                    down_cost += *n as u32;
-                    if i >= min {
-                        out.push(((x, y + i, Some(Vertical)), down_cost));
-                    }
-                } else { break; }
-            }
-
-            let mut up_cost: u32 = 0;
-            for i in 1..=max {
-                if let Some(n) = col.get((y - i) as usize) {
-                    up_cost += *n as u32;
-                    if i >= min {
-                        out.push(((x, y - i, Some(Vertical)), up_cost));
-                    }
-                } else { break; }
-            }
-            out
+    let Some(last) = hv else {
+        return vec![((x, y, Some(Horizontal)), 0), ((x, y, Some(Vertical)), 0)];
+    };
+    // We must always turn onto the other axis (in either direction).
+    let axis = last.perpendicular();
+    let mut out: Vec<(Node, u32)> = Vec::with_capacity(2 * (max - min + 1) as usize);
+    for d in [Dir::from_orientation(axis, true), Dir::from_orientation(axis, false)] {
+        let step = d.to_vector::<i64>();
+        let mut pos = Point::new(x as i64, y as i64);
+        let mut cost: u32 = 0;
+        for i in 1..=max {
+            pos += step;
+            // Off-grid positions are either negative (failing the cast) or out of bounds.
+            if let Some(n) = pos.try_cast::<usize>().and_then(|p| arr.get((p.y, p.x))) {
+                cost += *n as u32;
+                if i >= min {
+                    out.push(((pos.x as u32, pos.y as u32, Some(axis)), cost));
+                }
+            } else { break; }
         }
-        None => vec![((x, y, Some(Horizontal)), 0), ((x, y, Some(Vertical)), 0)],
     }
+    out
 }
@@ -5,44 +5,65 @@ use lazy_regex::regex_captures;
 use rpoly::RPoly;
 
 use crate::rpoly;
-use crate::xy::Dir;
-use crate::xy::Dir::{Down, Left, Right, Up};
+use crate::xy::{Dir, DirEncoding};
+use crate::xy::Orientation::Vertical;
 
-pub fn run(input: &str) -> (i64, i64) {
+pub fn run(input: &str) -> (i128, i128) {
     let lines: Vec<&str> = input.lines().collect();
     (parse_poly(&lines, &parse1).get_external_area(),
      parse_poly(&lines, &parse2).get_external_area())
 }
 
-fn parse1(s: &str) -> (Dir, i32) {
+fn parse1(s: &str) -> (Dir, i64) {
     let (_, dstr, lstr) = regex_captures!(r"([UDLR]) (\d+) \(#[0-9a-f]+\)", &s).unwrap();
-    let len = i32::from_str(lstr).unwrap();
-    match dstr {
-        "R" => (Right, len),
-        "D" => (Up, len),
-        "L" => (Left, len),
-        "U" => (Down, len),
-        _ => panic!("Bad input")
-    }
+    let dir = DirEncoding::Letters.parse(dstr.chars().next().unwrap()).unwrap();
+    (flip_y(dir), i64::from_str(lstr).unwrap())
 }
 
-fn parse2(s: &str) -> (Dir, i32) {
+fn parse2(s: &str) -> (Dir, i64) {
     let (_, hstr, dstr) = regex_captures!(r"[UDLR] \d+ \(#([0-9a-f]{5})([0-3])\)", &s).unwrap();
-    let len = i32::from_str_radix(hstr, 16).unwrap();
-    match dstr {
-        "0" => (Right, len),
-        "1" => (Up, len),
-        "2" => (Left, len),
-        "3" => (Down, len),
-        _ => panic!("Bad input")
-    }
+    let dir = DirEncoding::Digits.parse(dstr.chars().next().unwrap()).unwrap();
+    (flip_y(dir), i64::from_str_radix(hstr, 16).unwrap())
 }
 
-fn parse_poly(lines: &[&str], parse: &dyn Fn(&str) -> (Dir, i32)) -> RPoly {
+// The polygon is built with Y increasing upwards, so "down" in the dig plan is Up.
+fn flip_y(d: Dir) -> Dir {
+    if d.orientation() == Vertical { d.reverse() } else { d }
+}
+
+fn parse_poly(lines: &[&str], parse: &dyn Fn(&str) -> (Dir, i64)) -> RPoly<i64> {
     let mut poly = RPoly::new();
     for &s in lines {
         let (dir, len) = parse(s);
         poly.add_relative(dir, len);
     }
+    // A plan which crosses itself has no well-defined area, so must be rejected before measuring.
+    if let Err(e) = poly.validate() {
+        panic!("Invalid dig plan: {}", e);
+    }
     poly
 }
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "Invalid dig plan: Edges 2 and 5 intersect")]
+    fn test_crossing_plan() {
+        let input = indoc! {"
+            R 2 (#000000)
+            D 3 (#000000)
+            L 1 (#000000)
+            U 2 (#000000)
+            R 2 (#000000)
+            D 1 (#000000)
+            L 3 (#000000)
+            U 2 (#000000)"
+        };
+        run(input);
+    }
+}
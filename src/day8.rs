@@ -3,6 +3,8 @@ use std::collections::HashMap;
 use lazy_regex::regex_captures;
 use num_integer::Integer;
 
+use crate::cycle;
+
 pub fn run(input: &str) -> (u64, u64) {
     let lines: Vec<&str> = input.lines().collect();
     let dirn = &lines[0];
@@ -25,25 +27,16 @@ pub fn run(input: &str) -> (u64, u64) {
     }
 
     // (steps-to-end-state, cycle-length)
+    let dirs: Vec<char> = dirn.chars().collect();
     let mut loops: Vec<(u32, u32)> = vec![];
     for start in net.keys().filter(|&n| ends_with('A', n)) {
-        let mut trail: HashMap<Locn, u32> = HashMap::new();
-        let mut end: Option<u32> = None;
-        let mut count = 0;
-
-        let mut cur = *start;
-        for d in dirn.chars().cycle() {
-            count += 1;
-            cur = net.get(&cur).unwrap().get(d);
-            let loc = Locn { state: cur, index: count % dirn_count };
-            if let Some(&prev_count) = trail.get(&loc) {
-                // Assume we saw an end state before we looped.
-                loops.push((end.unwrap(), count - prev_count));
-                break;
-            }
-            trail.insert(loc, count);
-            if ends_with('Z', &cur) { end = Some(count); }
-        }
+        let history = cycle::hashed(Locn { state: *start, index: 0 }, |loc| Locn {
+            state: net.get(&loc.state).unwrap().get(dirs[loc.index as usize]),
+            index: (loc.index + 1) % dirn_count,
+        });
+        // Assume we saw an end state before we looped.
+        let end = history.states().iter().rposition(|loc| ends_with('Z', &loc.state)).unwrap();
+        loops.push((end as u32, history.period().cycle_len as u32));
     }
     // The offset in the input cycle at which we end (this is the same for all "ghosts"
     // since they work in lock step). If this is NOT zero, there's a *lot* more work to
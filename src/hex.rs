@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use num_traits::{PrimInt, Signed};
+
+use HexDir::{East, NorthEast, NorthWest, SouthEast, SouthWest, West};
+
+/// One of the six neighbour directions of a "pointy-topped" hexagon, where hexagons are arranged
+/// in horizontal rows and (as with `xy::Dir`) Y increases downwards.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum HexDir {
+    East,
+    SouthEast,
+    SouthWest,
+    West,
+    NorthWest,
+    NorthEast,
+}
+
+#[allow(unused)]
+impl HexDir {
+    /// Returns all directions, in clockwise order starting from `East`.
+    pub fn all() -> [HexDir; 6] {
+        [East, SouthEast, SouthWest, West, NorthWest, NorthEast]
+    }
+
+    /// Returns the direction rotated clockwise by the given number of 60 degree steps (negative
+    /// values rotate anti-clockwise).
+    pub fn rotate(&self, steps: i32) -> HexDir {
+        HexDir::all()[(*self as i32 + steps).rem_euclid(6) as usize]
+    }
+
+    /// Returns the opposite direction.
+    pub fn reverse(&self) -> HexDir {
+        self.rotate(3)
+    }
+
+    // The (q, r) axial offset of the neighbour in this direction.
+    fn offset(&self) -> (i8, i8) {
+        match self {
+            East => (1, 0),
+            SouthEast => (0, 1),
+            SouthWest => (-1, 1),
+            West => (-1, 0),
+            NorthWest => (0, -1),
+            NorthEast => (1, -1),
+        }
+    }
+}
+
+/// The position of a hexagon in "axial" coordinates.
+///
+/// The `q` coordinate increases to the east and the `r` coordinate increases to the south-east
+/// (i.e. along rows downwards). The implied third "cube" coordinate is `s = -q - r`, which makes
+/// distances and rotations symmetric.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Hex<I: PrimInt + Signed> {
+    pub q: I,
+    pub r: I,
+}
+
+#[allow(unused)]
+impl<I: PrimInt + Signed> Hex<I> {
+    pub fn new(q: I, r: I) -> Self { Hex { q, r } }
+
+    pub fn origin() -> Self { Hex { q: I::zero(), r: I::zero() } }
+
+    /// Returns the third cube coordinate.
+    pub fn s(&self) -> I {
+        -self.q - self.r
+    }
+
+    /// Converts "odd-r" offset coordinates (where odd rows are shifted half a hexagon to the east)
+    /// to axial coordinates.
+    pub fn from_offset(col: I, row: I) -> Hex<I> {
+        let two = I::one() + I::one();
+        let odd = row & I::one();
+        Hex { q: col - (row - odd) / two, r: row }
+    }
+
+    /// Converts to "odd-r" offset coordinates as `(col, row)`.
+    pub fn to_offset(self) -> (I, I) {
+        let two = I::one() + I::one();
+        let odd = self.r & I::one();
+        (self.q + (self.r - odd) / two, self.r)
+    }
+
+    pub fn neighbour(&self, dir: HexDir) -> Hex<I> {
+        self.move_by(I::one(), dir)
+    }
+
+    pub fn neighbours(&self) -> [Hex<I>; 6] {
+        HexDir::all().map(|d| self.neighbour(d))
+    }
+
+    /// Returns the hexagon `len` steps away in the given direction.
+    pub fn move_by(&self, len: I, dir: HexDir) -> Hex<I> {
+        let (dq, dr) = dir.offset();
+        Hex { q: self.q + len * I::from(dq).unwrap(), r: self.r + len * I::from(dr).unwrap() }
+    }
+
+    /// Returns the minimum number of steps between two hexagons.
+    pub fn distance(&self, other: &Hex<I>) -> I {
+        let two = I::one() + I::one();
+        ((self.q - other.q).abs() + (self.r - other.r).abs() + (self.s() - other.s()).abs()) / two
+    }
+
+    /// Returns this hexagon rotated clockwise about the given center by the given number of 60
+    /// degree steps (negative values rotate anti-clockwise).
+    pub fn rotate(&self, center: &Hex<I>, steps: i32) -> Hex<I> {
+        let (mut q, mut r) = (self.q - center.q, self.r - center.r);
+        for _ in 0..steps.rem_euclid(6) {
+            // Clockwise rotation of cube coordinates is (q, r, s) -> (-r, -s, -q).
+            (q, r) = (-r, q + r);
+        }
+        Hex { q: center.q + q, r: center.r + r }
+    }
+
+    /// Returns the hexagons at exactly `radius` steps from this one, clockwise from the eastmost
+    /// corner. The ring of radius zero is just this hexagon.
+    pub fn ring(&self, radius: I) -> impl Iterator<Item=Hex<I>> {
+        let center = *self;
+        let n = radius.to_usize().expect("Radius must be non-negative");
+        let sides = if n == 0 { 1 } else { 6 };
+        (0..sides).flat_map(move |side| {
+            let corner = center.move_by(radius, East.rotate(side));
+            // Walking along each side is a further 120 degrees clockwise.
+            let dir = East.rotate(side + 2);
+            (0..n.max(1)).map(move |k| corner.move_by(I::from(k).unwrap(), dir))
+        })
+    }
+
+    /// Returns all hexagons within `radius` steps of this one, ordered by ring (starting with this
+    /// hexagon).
+    pub fn spiral(&self, radius: I) -> impl Iterator<Item=Hex<I>> {
+        let center = *self;
+        let n = radius.to_usize().expect("Radius must be non-negative");
+        (0..=n).flat_map(move |k| center.ring(I::from(k).unwrap()))
+    }
+}
+
+/// A map of hexagons to characters, parsed from a textual "odd-r" offset layout.
+///
+/// In the text each hexagon is a single non-space character, separated by spaces, and odd rows
+/// (counting from zero) are indented by one extra space:
+/// ```text
+/// a b c
+///  d e f
+/// g h i
+/// ```
+/// Here `e` (col 1, row 1) is adjacent to `b`, `c`, `d`, `f`, `h` and `i`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HexGrid {
+    cells: HashMap<Hex<i32>, char>,
+}
+
+#[allow(unused)]
+impl HexGrid {
+    pub fn from_lines(s: &str) -> Result<HexGrid, HexGridError> {
+        let mut cells = HashMap::new();
+        for (row, line) in s.lines().enumerate() {
+            for (idx, c) in line.chars().enumerate().filter(|(_, c)| !c.is_whitespace()) {
+                let indent = row % 2;
+                if idx < indent || (idx - indent) % 2 != 0 {
+                    return Err(HexGridError::Misaligned { line: row, column: idx });
+                }
+                let col = ((idx - indent) / 2) as i32;
+                cells.insert(Hex::from_offset(col, row as i32), c);
+            }
+        }
+        if cells.is_empty() {
+            return Err(HexGridError::Empty);
+        }
+        Ok(HexGrid { cells })
+    }
+
+    pub fn get(&self, h: &Hex<i32>) -> Option<char> {
+        self.cells.get(h).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Returns all hexagons and their characters, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item=(&Hex<i32>, &char)> {
+        self.cells.iter()
+    }
+
+    /// Returns the neighbours of a hexagon which are present in the grid.
+    pub fn neighbours(&self, h: &Hex<i32>) -> impl Iterator<Item=(Hex<i32>, char)> + '_ {
+        h.neighbours().into_iter().filter_map(|n| self.get(&n).map(|c| (n, c)))
+    }
+}
+
+/// The reasons why text cannot be parsed as a `HexGrid`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum HexGridError {
+    /// There were no hexagons.
+    Empty,
+    /// A character (at a zero indexed line and column) was not at a valid offset for its row.
+    Misaligned { line: usize, column: usize },
+}
+
+impl Display for HexGridError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            HexGridError::Empty => write!(f, "Hex grid has no cells"),
+            HexGridError::Misaligned { line, column } =>
+                write!(f, "Misaligned hex cell at line {}, column {}", line, column),
+        }
+    }
+}
+
+impl Error for HexGridError {}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    #[test]
+    fn test_hex() {
+        let o: Hex<i32> = Hex::origin();
+        for d in HexDir::all() {
+            assert_eq!(o.neighbour(d).distance(&o), 1);
+            assert_eq!(o.neighbour(d).neighbour(d.reverse()), o);
+            // Rotating a neighbour around the origin matches rotating its direction.
+            assert_eq!(o.neighbour(d).rotate(&o, 1), o.neighbour(d.rotate(1)));
+        }
+        let h = Hex::new(3, -1);
+        assert_eq!(h.distance(&Hex::new(-1, 2)), 4);
+        assert_eq!(h.rotate(&Hex::new(1, 1), 6), h);
+        assert_eq!(h.rotate(&Hex::new(1, 1), -1), h.rotate(&Hex::new(1, 1), 5));
+        assert_eq!(Hex::from_offset(3, 5).to_offset(), (3, 5));
+        assert_eq!(Hex::from_offset(-2, -3).to_offset(), (-2, -3));
+    }
+
+    #[test]
+    fn test_ring_and_spiral() {
+        let c: Hex<i32> = Hex::new(2, -1);
+        assert_eq!(c.ring(0).collect::<Vec<_>>(), vec![c]);
+        for r in 1..5 {
+            let ring: Vec<_> = c.ring(r).collect();
+            assert_eq!(ring.len(), 6 * r as usize);
+            assert!(ring.iter().all(|h| h.distance(&c) == r));
+            // Consecutive hexagons in a ring (including the last and first) are adjacent.
+            assert!(ring.iter().zip(ring.iter().cycle().skip(1)).all(|(a, b)| a.distance(b) == 1));
+        }
+        assert_eq!(c.spiral(3).count(), 1 + 3 * 3 * 4);
+    }
+
+    #[test]
+    fn test_parse() {
+        let grid = HexGrid::from_lines("a b c\n d e f\ng h i").unwrap();
+        assert_eq!(grid.len(), 9);
+        let e = Hex::from_offset(1, 1);
+        assert_eq!(grid.get(&e), Some('e'));
+        let mut adjacent: Vec<char> = grid.neighbours(&e).map(|(_, c)| c).collect();
+        adjacent.sort();
+        assert_eq!(adjacent, vec!['b', 'c', 'd', 'f', 'h', 'i']);
+
+        assert_eq!(HexGrid::from_lines("a b\nc"), Err(HexGridError::Misaligned { line: 1, column: 0 }));
+        assert_eq!(HexGrid::from_lines(" \n"), Err(HexGridError::Empty));
+    }
+}
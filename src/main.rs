@@ -6,6 +6,8 @@ use std::time::Instant;
 
 mod util;
 mod rpoly;
+mod polygon;
+mod svg;
 mod day1;
 mod day2;
 mod day3;
@@ -16,8 +18,13 @@ mod day8;
 mod day10;
 mod day18;
 mod agrid;
+mod bgrid;
+mod cycle;
+mod trace;
 mod xy;
+mod xyz;
 mod day9;
+mod hex;
 mod day11;
 mod day12_alt;
 mod day13;
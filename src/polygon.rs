@@ -0,0 +1,136 @@
+use itertools::Itertools;
+use num_integer::Integer;
+use num_traits::{PrimInt, Signed};
+
+use crate::rpoly::{wide, RPoly};
+use crate::xy::Point;
+
+/// A simple polygon with integer coordinates, whose edges may have any orientation.
+///
+/// Unlike `RPoly`, edges are not restricted to be horizontal or vertical, and the final edge is
+/// always the implicit one from the last point back to the first. A polygon needs at least 3
+/// points for any of its measurements to be meaningful.
+///
+/// As with `RPoly`, coordinates can be any signed primitive integer type, and all lengths and
+/// areas are calculated and returned as `i128`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Polygon<I: PrimInt + Signed> {
+    points: Vec<Point<I>>,
+}
+
+/// The order in which a polygon's points are visited, as seen with Y increasing downwards (the
+/// same convention as `xy::Dir`).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Winding {
+    Clockwise,
+    CounterClockwise,
+}
+
+#[allow(unused)]
+impl<I: PrimInt + Signed> Polygon<I> {
+    /// Creates an empty `Polygon` with no points in it.
+    pub fn new() -> Polygon<I> { Polygon { points: vec![] } }
+
+    /// Adds a copy of the given point to the current polygon.
+    pub fn add_point(&mut self, p: &Point<I>) {
+        self.points.push(*p);
+    }
+
+    /// Adds the given `(x, y)` point to the current polygon.
+    pub fn add_xy(&mut self, x: I, y: I) {
+        self.points.push(Point { x, y });
+    }
+
+    pub fn points(&self) -> &[Point<I>] {
+        &self.points
+    }
+
+    /// Returns twice the signed area of the polygon, using the "shoelace" formula. Doubling the
+    /// area means the result is always an integer.
+    ///
+    /// The result is positive for `Clockwise` polygons and negative for `CounterClockwise` ones.
+    pub fn signed_double_area(&self) -> i128 {
+        self.edges().map(|(a, b)| wide(a.x) * wide(b.y) - wide(b.x) * wide(a.y)).sum()
+    }
+
+    /// Returns twice the area of the polygon (which may be an odd number, since the area of a
+    /// polygon with integer coordinates is a multiple of one half).
+    pub fn double_area(&self) -> i128 {
+        self.signed_double_area().abs()
+    }
+
+    /// Returns the direction in which the points of the polygon are visited, or `None` if the
+    /// polygon has no area.
+    pub fn winding(&self) -> Option<Winding> {
+        match self.signed_double_area().signum() {
+            1 => Some(Winding::Clockwise),
+            -1 => Some(Winding::CounterClockwise),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of integer points on the edges of the polygon (including its vertices).
+    ///
+    /// For an orthogonal polygon this is the same as `RPoly::get_perimeter()`.
+    pub fn boundary_points(&self) -> i128 {
+        // An edge from (0, 0) to (dx, dy) passes through gcd(dx, dy) points (excluding its start).
+        self.edges().map(|(a, b)| (wide(b.x) - wide(a.x)).gcd(&(wide(b.y) - wide(a.y)))).sum()
+    }
+
+    /// Returns the number of integer points strictly inside the polygon, using Pick's theorem
+    /// (`area = interior + boundary / 2 - 1`).
+    ///
+    /// For an orthogonal polygon this is the same as `RPoly::get_internal_area()`.
+    pub fn interior_points(&self) -> i128 {
+        (self.double_area() - self.boundary_points() + 2) / 2
+    }
+
+    // Pairs of consecutive points, including the final edge back to the start.
+    fn edges(&self) -> impl Iterator<Item=(&Point<I>, &Point<I>)> {
+        assert!(self.points.len() >= 3, "Polygon needs at least 3 points: {}", self.points.len());
+        self.points.iter().circular_tuple_windows()
+    }
+}
+
+impl<I: PrimInt + Signed> From<&RPoly<I>> for Polygon<I> {
+    fn from(poly: &RPoly<I>) -> Self {
+        Polygon { points: poly.points().to_vec() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rpoly::tests::u_shape;
+
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    #[test]
+    fn test_triangle() {
+        // A right angled triangle with a sloping edge through (2, 1) and (4, 2).
+        let mut poly: Polygon<i32> = Polygon::new();
+        poly.add_xy(0, 0);
+        poly.add_xy(6, 3);
+        poly.add_xy(0, 3);
+        assert_eq!(poly.double_area(), 18);
+        assert_eq!(poly.winding(), Some(Winding::Clockwise));
+        assert_eq!(poly.boundary_points(), 3 + 3 + 6);
+        // Interior points are (1, 1), (1, 2), (2, 2) and (3, 2).
+        assert_eq!(poly.interior_points(), 4);
+
+        let reversed = Polygon { points: poly.points().iter().rev().copied().collect() };
+        assert_eq!(reversed.signed_double_area(), -18);
+        assert_eq!(reversed.winding(), Some(Winding::CounterClockwise));
+        assert_eq!(reversed.interior_points(), 4);
+    }
+
+    #[test]
+    fn test_from_rpoly() {
+        let rpoly = u_shape();
+        let poly = Polygon::from(&rpoly);
+        assert_eq!(poly.double_area(), 2 * rpoly.get_area());
+        assert_eq!(poly.boundary_points(), rpoly.get_perimeter());
+        assert_eq!(poly.interior_points(), rpoly.get_internal_area());
+        assert_eq!(poly.interior_points() + poly.boundary_points(), rpoly.get_external_area());
+    }
+}
@@ -1,11 +1,21 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
 use std::iter::Chain;
 use std::slice::Iter;
 use std::vec;
 
 use itertools;
 use itertools::{Itertools, TupleWindows};
+use ndarray::Array2;
+use num_traits::{PrimInt, Signed};
 
-use crate::xy::{Dir, Orientation, Point};
+use crate::agrid::AGrid;
+use crate::bgrid::BGrid;
+use crate::polygon::{Polygon, Winding};
+use crate::util::PrimIter;
+use crate::xy::{Dir, Orientation, Point, Rect};
 use crate::xy::Dir::{Down, Left, Right, Up};
 use crate::xy::Orientation::{Horizontal, Vertical};
 
@@ -16,9 +26,12 @@ use crate::xy::Orientation::{Horizontal, Vertical};
 /// least 4 points/edges.
 ///
 /// Most operations can only be carried out on closed `RPoly` instances.
+///
+/// Coordinates can be any signed primitive integer type, but all lengths and areas are calculated
+/// and returned as `i128`, so they cannot overflow even for large `i64` coordinates.
 #[derive(Debug)]
-pub struct RPoly {
-    poly: Vec<Point<i32>>,
+pub struct RPoly<I: PrimInt + Signed> {
+    poly: Vec<Point<I>>,
 }
 
 // NOTE: The explicit <'a> lifetime exists to bind the lifetime of 'poly' to the devired structures
@@ -27,28 +40,33 @@ pub struct RPoly {
 // of RPoly and connot outlive it (but it's not directly held by an instance, and only created in
 // methods which need it).
 #[allow(unused)]
-impl<'a> RPoly {
+impl<'a, I: PrimInt + Signed> RPoly<I> {
     /// Creates an empty `RPoly` with no points in it.
-    pub fn new() -> RPoly { RPoly { poly: vec![] } }
+    pub fn new() -> RPoly<I> { RPoly { poly: vec![] } }
 
     /// Adds a copy of the given point to the current polygon.
-    pub fn add_point(&mut self, p: &Point<i32>) {
+    pub fn add_point(&mut self, p: &Point<I>) {
         self.poly.push(*p);
     }
 
     /// Adds the given `(x, y)` point to the current polygon.
-    pub fn add_xy(&mut self, x: i32, y: i32) {
+    pub fn add_xy(&mut self, x: I, y: I) {
         self.poly.push(Point { x, y });
     }
 
     /// Adds a new point to the polygon relative to the last added point or, if the polygon is
     /// empty, the origin.
-    pub fn add_relative(&mut self, dir: Dir, len: i32) {
+    pub fn add_relative(&mut self, dir: Dir, len: I) {
         self.poly.push(self.poly.last().unwrap_or(&Point::origin()).move_by(len, dir));
     }
 
+    /// Returns the points of the polygon, in the order they were added.
+    pub fn points(&self) -> &[Point<I>] {
+        &self.poly
+    }
+
     /// Returns the perimeter of a closed RPoly.
-    pub fn get_perimeter(&self) -> i64 {
+    pub fn get_perimeter(&self) -> i128 {
         RPoly::sum_edge_lengths(&self.get_orthogonal_edges())
     }
 
@@ -56,7 +74,7 @@ impl<'a> RPoly {
     /// polygon in the X/Y plane if the points of the polygon are aligned to the X/Y coordinates.
     ///
     /// Aligned area is the natural area of a polygon if edges have no width.
-    pub fn get_area(&self) -> i64 {
+    pub fn get_area(&self) -> i128 {
         RPoly::get_edge_aligned_area(&self.get_orthogonal_edges())
     }
 
@@ -81,9 +99,9 @@ impl<'a> RPoly {
     /// 0 |___.___.___.___
     ///   0   1   2   3
     /// ```
-    pub fn get_external_area(&'a self) -> i64 {
+    pub fn get_external_area(&'a self) -> i128 {
         // We must capture the explicit lifetime for 'self' because it also applies to 'edges'.
-        let edges: Vec<Edge<'a>> = self.get_orthogonal_edges();
+        let edges: Vec<Edge<'a, I>> = self.get_orthogonal_edges();
         let perimeter = RPoly::sum_edge_lengths(&edges);
         let aligned_area = RPoly::get_edge_aligned_area(&edges);
         aligned_area + 1 + (perimeter / 2)
@@ -110,31 +128,290 @@ impl<'a> RPoly {
     /// 0 |___.___.___.___
     ///   0   1   2   3
     /// ```
-    pub fn get_internal_area(&'a self) -> i64 {
+    pub fn get_internal_area(&'a self) -> i128 {
         // We must capture the explicit lifetime for 'self' because it also applies to 'edges'.
-        let edges: Vec<Edge<'a>> = self.get_orthogonal_edges();
+        let edges: Vec<Edge<'a, I>> = self.get_orthogonal_edges();
         let perimeter = RPoly::sum_edge_lengths(&edges);
         let aligned_area = RPoly::get_edge_aligned_area(&edges);
         aligned_area + 1 - (perimeter / 2)
     }
 
-    fn get_orthogonal_edges(&'a self) -> Vec<Edge<'a>> {
-        // We must capture the explicit lifetime for 'self' because it also applies to 'edges'.
+    /// Returns whether a point is inside, outside or on the boundary of a closed `RPoly`, where
+    /// edges have no width (so "inside" points are those counted by `get_internal_area()`).
+    pub fn classify(&'a self, p: &Point<I>) -> Location {
+        RPoly::classify_with_edges(&self.get_orthogonal_edges(), p)
+    }
+
+    // Implements classify() for the edges of a closed polygon, so that callers classifying many
+    // points need only get the edges once.
+    fn classify_with_edges(edges: &[Edge<I>], p: &Point<I>) -> Location {
+        if edges.iter().any(|e| e.bounds().contains(p)) {
+            return Location::Boundary;
+        }
+        // Cast a ray to the right, and count the vertical edges it crosses.
+        let crossings = edges.iter()
+            .filter(|e| e.orientation() == Vertical && e.start().x > p.x && e.spans_y(p.y))
+            .count();
+        if crossings % 2 == 1 { Location::Inside } else { Location::Outside }
+    }
+
+    /// Classifies every point of the given rectangle (as for `classify()`), returning an array
+    /// indexed by `(y - min_y, x - min_x)`, matching the layout of `AGrid`.
+    ///
+    /// This processes one row at a time, only considering the vertical edges which cross the row,
+    /// so is far cheaper than classifying each point individually.
+    pub fn classify_rect(&'a self, rect: &Rect<I>) -> Array2<Location> {
+        let edges: Vec<Edge<'a, I>> = self.get_orthogonal_edges();
+        let vedges: Vec<&Edge<'a, I>> = edges.iter().filter(|e| e.orientation() == Vertical).collect();
+        let (min, max) = (rect.top_left(), rect.bottom_right());
+        let width = (wide(max.x) - wide(min.x) + 1) as usize;
+        let height = (wide(max.y) - wide(min.y) + 1) as usize;
+        let mut out = Array2::from_elem((height, width), Location::Outside);
+        for (row, y) in PrimIter::new(min.y, max.y, I::one()).enumerate() {
+            let crossings: Vec<i128> =
+                vedges.iter().filter(|e| e.spans_y(y)).map(|e| wide(e.start().x)).sorted().collect();
+            // Points after an odd number of crossings (from the left) are inside. Points on the
+            // crossing edges themselves are overwritten as boundary points below.
+            for (&lhs, &rhs) in crossings.iter().tuples() {
+                let lo = (lhs + 1).max(wide(min.x)) - wide(min.x);
+                let hi = rhs.min(wide(max.x)) - wide(min.x);
+                for x in lo..=hi {
+                    out[(row, x as usize)] = Location::Inside;
+                }
+            }
+        }
+        for e in edges.iter() {
+            if let Some(r) = e.bounds().intersection(rect) {
+                for p in r.points() {
+                    out[((wide(p.y) - wide(min.y)) as usize, (wide(p.x) - wide(min.x)) as usize)] = Location::Boundary;
+                }
+            }
+        }
+        out
+    }
+
+    /// Returns the smallest rectangle containing every point of the polygon.
+    pub fn bounds(&self) -> Rect<I> {
+        Rect::bounding(&self.poly).expect("Polygon has no points")
+    }
+
+    /// Renders a closed `RPoly` as an `AGrid` covering `bounds()`, using the given function to
+    /// choose the character for each point's location. Grid position `(0, 0)` corresponds to the
+    /// top left of the bounds.
+    pub fn to_grid<F: Fn(Location) -> char>(&'a self, render: F) -> AGrid {
+        AGrid { grid: self.classify_rect(&self.bounds()).mapv(render) }
+    }
+
+    /// Returns a mask of a closed `RPoly` covering `bounds()` (in the same layout as `to_grid()`),
+    /// with bits set for every point whose location matches the predicate.
+    ///
+    /// Matching only `Inside` points gives a mask whose size is `get_internal_area()`, and
+    /// matching `Inside` or `Boundary` points gives a mask whose size is `get_external_area()`.
+    pub fn to_mask<F: Fn(Location) -> bool>(&'a self, pred: F) -> BGrid {
+        let classes = self.classify_rect(&self.bounds());
+        let (height, width) = classes.dim();
+        let mut mask = BGrid::new(width, height);
+        for ((y, x), _) in classes.indexed_iter().filter(|(_, &c)| pred(c)) {
+            mask.set_xy(x, y, true);
+        }
+        mask
+    }
+
+    /// Returns a set of rectangles which exactly cover a closed `RPoly`, overlapping only along
+    /// their edges, ordered by their top left corners.
+    ///
+    /// Rectangles are formed from the horizontal strips between the Y coordinates of the polygon's
+    /// points, where strips covering the same X range are joined vertically. This is fast, but may
+    /// produce more rectangles than necessary (see `to_min_rects()`).
+    ///
+    /// As with `get_area()`, edges have no width, so the aligned area of each rectangle is
+    /// `(width - 1) * (height - 1)` (rather than `Rect::area()`, which counts points).
+    pub fn to_rects(&'a self) -> Vec<Rect<I>> {
+        let mut rects: Vec<Rect<I>> = vec![];
+        // Rectangles which reach the bottom of the previous slab, and may be extended downwards.
+        let mut open: Vec<Rect<I>> = vec![];
+        for slab in RPoly::get_slabs(&self.get_orthogonal_edges()) {
+            let mut next: Vec<Rect<I>> = vec![];
+            for &(x_lo, x_hi) in &slab.spans {
+                let joined = open.iter()
+                    .position(|r| r.top_left().x == x_lo && r.bottom_right() == Point::new(x_hi, slab.y_lo));
+                let top = match joined {
+                    Some(i) => open.swap_remove(i).top_left().y,
+                    None => slab.y_lo,
+                };
+                next.push(Rect::from_corners(Point::new(x_lo, top), Point::new(x_hi, slab.y_hi)));
+            }
+            rects.append(&mut open);
+            open = next;
+        }
+        rects.append(&mut open);
+        rects.sort_by_key(|r| (r.top_left().y, r.top_left().x));
+        rects
+    }
+
+    /// Returns the smallest possible set of rectangles which exactly cover a closed `RPoly`,
+    /// overlapping only along their edges, ordered by their top left corners (see `to_rects()`).
+    ///
+    /// This cuts the polygon along the largest set of non-intersecting "chords" joining pairs of
+    /// concave corners (found by bipartite matching), and then cuts from each remaining concave
+    /// corner until meeting the boundary or another cut. This works on a grid of the polygon's
+    /// distinct X and Y coordinates, so takes time proportional to the square of the number of
+    /// points.
+    pub fn to_min_rects(&'a self) -> Vec<Rect<I>> {
+        let slabs = RPoly::get_slabs(&self.get_orthogonal_edges());
+        CutGrid::new(&self.poly, &slabs).into_rects()
+    }
+
+    /// Returns the regions covered by either this or the other closed `RPoly`.
+    ///
+    /// Polygons which share part of an edge are merged into a single region, with the shared part
+    /// of the edge removed, but polygons which only touch at a corner are left as separate regions.
+    pub fn union(&'a self, other: &'a RPoly<I>) -> Vec<Region<I>> {
+        self.combine(other, |a, b| a || b)
+    }
+
+    /// Returns the regions covered by both this and the other closed `RPoly`.
+    pub fn intersection(&'a self, other: &'a RPoly<I>) -> Vec<Region<I>> {
+        self.combine(other, |a, b| a && b)
+    }
+
+    /// Returns the regions covered by this closed `RPoly` but not the other.
+    pub fn difference(&'a self, other: &'a RPoly<I>) -> Vec<Region<I>> {
+        self.combine(other, |a, b| a && !b)
+    }
+
+    // Applies a boolean operation to the slabs of both polygons, and converts the resulting slabs
+    // back to polygons.
+    fn combine<F: Fn(bool, bool) -> bool>(&'a self, other: &'a RPoly<I>, op: F) -> Vec<Region<I>> {
+        let lhs = RPoly::get_slabs(&self.get_orthogonal_edges());
+        let rhs = RPoly::get_slabs(&other.get_orthogonal_edges());
+        to_regions(&combine_slabs(&lhs, &rhs, &op))
+    }
+
+    /// Checks that this is a valid closed `RPoly`, with at least 4 points, where every edge
+    /// (including the implicit final edge) is orthogonal and non-zero, and edges alternate between
+    /// horizontal and vertical. The polygon must also be "simple", with no edges crossing,
+    /// overlapping or touching any edge other than their immediate neighbours.
+    ///
+    /// Edge indices in any error are those of the edge's start point.
+    ///
+    /// Other operations only repeat the cheaper structural checks (panicking if they fail), so a
+    /// self-intersecting polygon built point by point is only rejected here, or by
+    /// `RPolyBuilder::try_close()`.
+    pub fn validate(&self) -> Result<(), PolyError> {
+        self.validate_structure()?;
+        let edges: Vec<Edge<I>> = self.edges().collect();
+        if let Some((first, second)) = RPoly::find_intersection(&edges) {
+            return Err(PolyError::SelfIntersecting { first, second });
+        }
+        Ok(())
+    }
+
+    // Checks everything in validate() except for self-intersection, in linear time.
+    fn validate_structure(&self) -> Result<(), PolyError> {
+        let len = self.poly.len();
+        if len < 4 {
+            return Err(PolyError::TooFewPoints(len));
+        }
+        let edges: Vec<Edge<I>> = self.edges().collect();
+        for (index, e) in edges.iter().enumerate() {
+            if e.start() == e.end() {
+                return Err(PolyError::ZeroLengthEdge { index });
+            }
+            if e.start().x != e.end().x && e.start().y != e.end().y {
+                return Err(if index == len - 1 { PolyError::NotClosed } else { PolyError::DiagonalEdge { index } });
+            }
+        }
+        // Cyclic pairs start with (last, first), so index 0 is the pair ending at the first edge.
+        if let Some(i) = to_cyclic_pairs(&edges).position(|(a, b)| a.orientation() == b.orientation()) {
+            return Err(PolyError::CollinearEdges { index: (i + len - 1) % len });
+        }
+        Ok(())
+    }
+
+    // Returns the indices of a pair of non-adjacent orthogonal edges which share any point, by
+    // sweeping a vertical line from left to right. This is the first such pair found by the sweep
+    // (i.e. the pair with the leftmost point in common, in most cases).
+    fn find_intersection(edges: &[Edge<I>]) -> Option<(usize, usize)> {
+        let len = edges.len();
+        let pair = |i: usize, j: usize| Some((i.min(j), i.max(j)));
+        let adjacent = |i: usize, j: usize| (i + 1) % len == j || (j + 1) % len == i;
+        // Events are (x, kind, y, index), where at the same X, horizontal edges are added to the
+        // active set (kind 0) before vertical edges are checked (kind 1), and are removed (kind 2)
+        // only after that, so that edges touching at their ends are seen to intersect.
+        let mut events: Vec<(I, u8, I, usize)> = vec![];
+        for (i, e) in edges.iter().enumerate() {
+            let (min, max) = (e.bounds().top_left(), e.bounds().bottom_right());
+            if e.orientation() == Horizontal {
+                events.push((min.x, 0, min.y, i));
+                events.push((max.x, 2, min.y, i));
+            } else {
+                events.push((min.x, 1, min.y, i));
+            }
+        }
+        events.sort();
+
+        // Active horizontal edges as (y, index).
+        let mut active: BTreeSet<(I, usize)> = BTreeSet::new();
+        // The last vertical edge seen, as (x, max_y, index). Vertical edges at the same X are seen
+        // in order of their minimum Y so, until an overlap is found, this is also the one reaching
+        // furthest down, and any later edge at the same X starting above its end overlaps it.
+        let mut furthest: Option<(I, I, usize)> = None;
+        for (x, kind, y, i) in events {
+            match kind {
+                0 => {
+                    // Any active edge on the same line overlaps this one (by at least one point).
+                    if let Some(&(_, j)) = active.range((y, 0)..=(y, usize::MAX)).next() {
+                        return pair(i, j);
+                    }
+                    active.insert((y, i));
+                }
+                1 => {
+                    let max_y = edges[i].bounds().bottom_right().y;
+                    if let Some((fx, fy, j)) = furthest {
+                        if fx == x && fy >= y {
+                            return pair(i, j);
+                        }
+                    }
+                    furthest = Some((x, max_y, i));
+                    let crossing = active.range((y, 0)..=(max_y, usize::MAX)).find(|&&(_, j)| !adjacent(i, j));
+                    if let Some(&(_, j)) = crossing {
+                        return pair(i, j);
+                    }
+                }
+                _ => { active.remove(&(y, i)); }
+            }
+        }
+        None
+    }
+
+    fn edges(&'a self) -> impl Iterator<Item=Edge<'a, I>> {
         let len = self.poly.len();
-        assert!(len >= 4, "Not enough points!");
-        let edges: Vec<Edge<'a>> =
-            (0..len).map(|i| Edge { start: &self.poly[i], end: &self.poly[(i + 1) % len] }).collect();
-        assert!(to_cyclic_pairs(&edges).all(|(a, b)| a.orientation() != b.orientation()));
-        edges
+        (0..len).map(move |i| Edge { start: &self.poly[i], end: &self.poly[(i + 1) % len] })
+    }
+
+    fn get_orthogonal_edges(&'a self) -> Vec<Edge<'a, I>> {
+        // We must capture the explicit lifetime for 'self' because it also applies to 'edges'.
+        if let Err(e) = self.validate_structure() {
+            panic!("Invalid polygon: {}", e);
+        }
+        self.edges().collect()
     }
 
-    fn sum_edge_lengths(edges: &Vec<Edge>) -> i64 {
+    fn sum_edge_lengths(edges: &Vec<Edge<I>>) -> i128 {
         // Here there's no need to care about the lifetime of 'edges' since the result value is
         // not bound to it.
-        edges.iter().map(|e| e.length() as i64).sum()
+        edges.iter().map(|e| e.length()).sum()
+    }
+
+    fn get_edge_aligned_area(edges: &'a Vec<Edge<'a, I>>) -> i128 {
+        RPoly::get_slabs(edges).iter().map(|s| s.get_area()).sum()
     }
 
-    fn get_edge_aligned_area(edges: &'a Vec<Edge<'a>>) -> i64 {
+    // Returns the horizontal slabs of a closed polygon, in order of increasing Y. Every Y
+    // coordinate of the polygon's points is the start or end of some slab, and consecutive slabs
+    // always meet (since a closed polygon covers every Y between its minimum and maximum).
+    fn get_slabs(edges: &'a Vec<Edge<'a, I>>) -> Vec<Slab<I>> {
         // List of vertical edges in "Polygon order".
         //
         // Here, 'vedges' is borrowing the edge references for 'edges' and must therefore reflect
@@ -146,7 +423,7 @@ impl<'a> RPoly {
         //
         // If 'Edge' were copyable we could make 'vedges: Vec<Edge<'a>>' but would still need the
         // lifetime specified for 'Edge'
-        let vedges: Vec<&'a Edge<'a>> = edges.iter().filter(|e: &&'a Edge<'a>| e.orientation() == Vertical).collect();
+        let vedges: Vec<&'a Edge<'a, I>> = edges.iter().filter(|e: &&'a Edge<'a, I>| e.orientation() == Vertical).collect();
 
         // Y-coordinates at which changes to followers must be made.
         //
@@ -159,14 +436,14 @@ impl<'a> RPoly {
         // * cur==up, nxt==down: Remove cur/nxt as edge followers
         // * cur==up, nxt==up: Replace cur with nxt
         // * cur==down, nxt==down: Replace nxt with cur
-        let y_values: Vec<(i32, usize)> =
+        let y_values: Vec<(I, usize)> =
             vedges.iter().enumerate().map(|(vi, ve)| (ve.end().y, vi)).sorted().collect();
 
         // (x, idx)
-        let mut active: Vec<(i32, usize)> = vec![];
-        let mut y_prv: i32 = y_values[0].0;
+        let mut active: Vec<(I, usize)> = vec![];
+        let mut y_prv: I = y_values[0].0;
         let mut y_idx: usize = 0;
-        let mut total_area: i64 = 0;
+        let mut slabs: Vec<Slab<I>> = vec![];
         'main: loop {
             let (mut y_cur, mut vi) = y_values[y_idx];
             assert!(y_cur >= y_prv);
@@ -179,24 +456,172 @@ impl<'a> RPoly {
                 if y_idx == y_values.len() { break 'main; }
                 (y_cur, vi) = y_values[y_idx];
             }
-            let width: i32 = active.iter()
-                .sorted().tuples().map(|(&lhs, &rhs)| rhs.0 - lhs.0).sum();
-            total_area += ((y_cur - y_prv) as i64) * (width as i64);
+            let spans = active.iter().sorted().tuples().map(|(&lhs, &rhs)| (lhs.0, rhs.0)).collect();
+            slabs.push(Slab { y_lo: y_prv, y_hi: y_cur, spans });
             y_prv = y_cur;
         }
         assert!(active.is_empty());
-        total_area
+        slabs
     }
 }
 
+// A horizontal strip of a polygon, where the polygon covers the same X intervals (spans) for every
+// Y between 'y_lo' and 'y_hi'. Spans are ordered, and are separated by gaps of at least 1 unit.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Slab<I: PrimInt> {
+    y_lo: I,
+    y_hi: I,
+    spans: Vec<(I, I)>,
+}
+
+impl<I: PrimInt> Slab<I> {
+    fn get_area(&self) -> i128 {
+        let width: i128 = self.spans.iter().map(|&(lhs, rhs)| wide(rhs) - wide(lhs)).sum();
+        (wide(self.y_hi) - wide(self.y_lo)) * width
+    }
+}
+
+/// Where a point lies relative to a closed `RPoly`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Location {
+    Inside,
+    Boundary,
+    Outside,
+}
+
+/// An area bounded by an outer `RPoly`, excluding the areas inside any holes.
+///
+/// When produced by the boolean operations on `RPoly`, the outer polygon's points are in clockwise
+/// order and the holes' points are in anti-clockwise order (as seen with (0, 0) at the top left).
+/// Holes may touch the outer polygon, or each other, at corners.
+#[derive(Debug)]
+pub struct Region<I: PrimInt + Signed> {
+    pub outer: RPoly<I>,
+    pub holes: Vec<RPoly<I>>,
+}
+
+#[allow(unused)]
+impl<I: PrimInt + Signed> Region<I> {
+    /// Returns the aligned area of the region (see `RPoly::get_area()`).
+    pub fn get_area(&self) -> i128 {
+        self.outer.get_area() - self.holes.iter().map(|h| h.get_area()).sum::<i128>()
+    }
+}
+
+/// The reasons why a sequence of points is not a valid closed `RPoly`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PolyError {
+    /// Fewer than 4 points (which is the minimum for a closed polygon).
+    TooFewPoints(usize),
+    /// The edge starting at the given point index is neither horizontal nor vertical.
+    DiagonalEdge { index: usize },
+    /// The edge starting at the given point index has zero length.
+    ZeroLengthEdge { index: usize },
+    /// The edges starting at the given point index, and the one after it, have the same
+    /// orientation (i.e. the point between them is redundant).
+    CollinearEdges { index: usize },
+    /// The implicit final edge, from the last point back to the first, is not orthogonal.
+    NotClosed,
+    /// The edges starting at the given point indices (in ascending order) cross, overlap or touch.
+    SelfIntersecting { first: usize, second: usize },
+}
+
+impl Display for PolyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PolyError::TooFewPoints(n) => write!(f, "Not enough points: {} (need at least 4)", n),
+            PolyError::DiagonalEdge { index } => write!(f, "Edge {} is not orthogonal", index),
+            PolyError::ZeroLengthEdge { index } => write!(f, "Edge {} has zero length", index),
+            PolyError::CollinearEdges { index } =>
+                write!(f, "Edges {} and {} have the same orientation", index, index + 1),
+            PolyError::NotClosed => write!(f, "Final edge is not orthogonal"),
+            PolyError::SelfIntersecting { first, second } => write!(f, "Edges {} and {} intersect", first, second),
+        }
+    }
+}
+
+impl Error for PolyError {}
+
+/// Collects points for an `RPoly`, normalising them to remove redundant points.
+///
+/// Input such as dig plans or traced outlines often contains repeated points (zero-length edges),
+/// or several moves in the same direction (collinear runs), neither of which `RPoly` accepts.
+#[derive(Debug, Default)]
+pub struct RPolyBuilder<I: PrimInt + Signed> {
+    points: Vec<Point<I>>,
+}
+
+#[allow(unused)]
+impl<I: PrimInt + Signed> RPolyBuilder<I> {
+    pub fn new() -> RPolyBuilder<I> { RPolyBuilder { points: vec![] } }
+
+    /// Adds a copy of the given point.
+    pub fn add_point(&mut self, p: &Point<I>) {
+        self.push(*p);
+    }
+
+    /// Adds the given `(x, y)` point.
+    pub fn add_xy(&mut self, x: I, y: I) {
+        self.push(Point { x, y });
+    }
+
+    /// Adds a new point relative to the last added point or, if there are no points, the origin.
+    pub fn add_relative(&mut self, dir: Dir, len: I) {
+        self.push(self.points.last().unwrap_or(&Point::origin()).move_by(len, dir));
+    }
+
+    // Adds a point, dropping or replacing the last point if it would be redundant.
+    fn push(&mut self, p: Point<I>) {
+        if self.points.last() == Some(&p) {
+            return;
+        }
+        let len = self.points.len();
+        if len >= 2 && collinear(&self.points[len - 2], &self.points[len - 1], &p) {
+            self.points.pop();
+            // A run which doubles back to the previous point leaves a zero-length edge.
+            if self.points.last() == Some(&p) {
+                return;
+            }
+        }
+        self.points.push(p);
+    }
+
+    /// Normalises the points where the polygon wraps around (so the final point may be a repeat of
+    /// the first point) and returns the validated polygon.
+    pub fn try_close(mut self) -> Result<RPoly<I>, PolyError> {
+        let pts = &mut self.points;
+        loop {
+            let len = pts.len();
+            if len < 3 {
+                break;
+            }
+            if pts[len - 1] == pts[0] || collinear(&pts[len - 2], &pts[len - 1], &pts[0]) {
+                pts.pop();
+            } else if collinear(&pts[len - 1], &pts[0], &pts[1]) {
+                pts.remove(0);
+            } else {
+                break;
+            }
+        }
+        let poly = RPoly { poly: self.points };
+        poly.validate()?;
+        Ok(poly)
+    }
+}
+
+// Whether three points lie on the same horizontal or vertical line.
+fn collinear<I: PrimInt>(a: &Point<I>, b: &Point<I>, c: &Point<I>) -> bool {
+    (a.x == b.x && b.x == c.x) || (a.y == b.y && b.y == c.y)
+}
+
 // Demonstrates usage of lifetimes for what is effectively an inner struct of RPoly.
 //
 // An edge directly references the points within an RPoly's vector. This means the edge must have
 // an explicit named lifetime so to be associated with a specific RPoly instance.
 #[derive(Debug)]
-struct Edge<'a> {
-    start: &'a Point<i32>,
-    end: &'a Point<i32>,
+struct Edge<'a, I: PrimInt + Signed> {
+    start: &'a Point<I>,
+    end: &'a Point<I>,
 }
 
 // https://stackoverflow.com/questions/39355984/what-does-the-first-explicit-lifetime-specifier-on-an-impl-mean
@@ -208,22 +633,22 @@ struct Edge<'a> {
 // explicit mentions of lifetimes.
 //
 //    ,,-- This indicates an explicit lifetime given by the compiler.
-//    vv       vv-- Which is associated to the lifetime of the struct and, in turn, its contents.
-impl<'a> Edge<'a> {
-    fn start(&self) -> &Point<i32> {
+//    vv                            vv-- Associated to the lifetime of the struct and its contents.
+impl<'a, I: PrimInt + Signed> Edge<'a, I> {
+    fn start(&self) -> &Point<I> {
         &self.start
     }
 
-    fn end(&self) -> &Point<i32> {
+    fn end(&self) -> &Point<I> {
         &self.end
     }
 
-    fn width(&self) -> i32 {
-        (self.end().x - self.start().x).abs()
+    fn width(&self) -> i128 {
+        (wide(self.end().x) - wide(self.start().x)).abs()
     }
 
-    fn height(&self) -> i32 {
-        (self.end().y - self.start().y).abs()
+    fn height(&self) -> i128 {
+        (wide(self.end().y) - wide(self.start().y)).abs()
     }
 
     fn orientation(&self) -> Orientation {
@@ -233,19 +658,366 @@ impl<'a> Edge<'a> {
     fn direction(&self) -> Dir {
         let start = self.start();
         let end = self.end();
-        let h_offset = end.x - start.x;
-        let v_offset = end.y - start.y;
-        if h_offset != 0 {
-            if h_offset > 0 { Right } else { Left }
+        if end.x != start.x {
+            if end.x > start.x { Right } else { Left }
         } else {
-            if v_offset > 0 { Down } else { Up }
+            if end.y > start.y { Down } else { Up }
         }
     }
 
-    fn length(&self) -> i32 {
+    fn length(&self) -> i128 {
         let w = self.width();
         if w != 0 { w } else { self.height() }
     }
+
+    // The rectangle covering all points on the edge.
+    fn bounds(&self) -> Rect<I> {
+        Rect::from_corners(*self.start(), *self.end())
+    }
+
+    // Whether a vertical edge crosses the horizontal line at 'y'. This is "half open" (including
+    // the upper end but not the lower end) so a line through a vertex only counts one crossing
+    // for edges continuing across the line, and zero or two for edges forming a "U" turn.
+    fn spans_y(&self, y: I) -> bool {
+        let (lo, hi) = (self.start().y.min(self.end().y), self.start().y.max(self.end().y));
+        lo <= y && y < hi
+    }
+}
+
+// Combines two sets of slabs with a boolean operation, splitting them so that all resulting slabs
+// have the same Y range in both inputs. Empty slabs are omitted.
+fn combine_slabs<I: PrimInt, F: Fn(bool, bool) -> bool>(lhs: &[Slab<I>], rhs: &[Slab<I>], op: &F) -> Vec<Slab<I>> {
+    // Slabs are ordered and don't overlap, so the one containing 'y' (if any) can be found by a
+    // binary search.
+    let spans_at = |slabs: &'_ [Slab<I>], y: I| -> Vec<(I, I)> {
+        let i = slabs.partition_point(|s| s.y_hi <= y);
+        if i < slabs.len() && slabs[i].y_lo <= y { slabs[i].spans.clone() } else { vec![] }
+    };
+    lhs.iter().chain(rhs.iter())
+        .flat_map(|s| [s.y_lo, s.y_hi])
+        .sorted().dedup()
+        .tuple_windows()
+        .map(|(y_lo, y_hi)| Slab { y_lo, y_hi, spans: combine_spans(&spans_at(lhs, y_lo), &spans_at(rhs, y_lo), op) })
+        .filter(|s| !s.spans.is_empty())
+        .collect()
+}
+
+// Combines two ordered lists of spans with a boolean operation, merging any resulting spans which
+// meet.
+fn combine_spans<I: PrimInt, F: Fn(bool, bool) -> bool>(lhs: &[(I, I)], rhs: &[(I, I)], op: &F) -> Vec<(I, I)> {
+    let mut out: Vec<(I, I)> = vec![];
+    let xs = lhs.iter().chain(rhs.iter()).flat_map(|&(lo, hi)| [lo, hi]).sorted().dedup();
+    for (x_lo, x_hi) in xs.tuple_windows() {
+        if op(spans_cover(lhs, x_lo), spans_cover(rhs, x_lo)) {
+            match out.last_mut() {
+                Some(last) if last.1 == x_lo => last.1 = x_hi,
+                _ => out.push((x_lo, x_hi)),
+            }
+        }
+    }
+    out
+}
+
+// A grid of the cells between the distinct X and Y coordinates of a polygon's points, for cutting
+// the polygon into a minimal set of rectangles. Cell (col, row) lies between 'xs[col]' and
+// 'xs[col + 1]', and between 'ys[row]' and 'ys[row + 1]', while grid point (i, j) is at
+// 'xs[i]' and 'ys[j]'.
+struct CutGrid<I: PrimInt> {
+    xs: Vec<I>,
+    ys: Vec<I>,
+    covered: Vec<Vec<bool>>,
+    // Cuts along the vertical grid line 'i' in row 'r', indexed '[r][i]'.
+    vcuts: Vec<Vec<bool>>,
+    // Cuts along the horizontal grid line 'j' in column 'c', indexed '[j][c]'.
+    hcuts: Vec<Vec<bool>>,
+}
+
+// A cut between two grid points on the same line, as (line, from, to) with 'from < to'.
+type Chord = (usize, usize, usize);
+
+impl<I: PrimInt> CutGrid<I> {
+    fn new(points: &[Point<I>], slabs: &[Slab<I>]) -> CutGrid<I> {
+        let xs: Vec<I> = points.iter().map(|p| p.x).sorted().dedup().collect();
+        let ys: Vec<I> = points.iter().map(|p| p.y).sorted().dedup().collect();
+        // Slabs are split at every Y coordinate of the polygon, so there is one per row.
+        assert_eq!(slabs.len(), ys.len() - 1);
+        let covered: Vec<Vec<bool>> = slabs.iter()
+            .map(|s| xs[..xs.len() - 1].iter().map(|&x| spans_cover(&s.spans, x)).collect())
+            .collect();
+        let (w, h) = (xs.len() - 1, ys.len() - 1);
+        CutGrid { xs, ys, covered, vcuts: vec![vec![false; w + 1]; h], hcuts: vec![vec![false; w]; h + 1] }
+    }
+
+    fn width(&self) -> usize { self.xs.len() - 1 }
+
+    fn height(&self) -> usize { self.ys.len() - 1 }
+
+    // Whether the cell is covered, treating cells outside the grid as uncovered.
+    fn is_covered(&self, col: usize, row: usize) -> bool {
+        row < self.height() && col < self.width() && self.covered[row][col]
+    }
+
+    // Whether the vertical unit segment on line 'i' in row 'r' has covered cells on both sides.
+    fn v_interior(&self, i: usize, r: usize) -> bool {
+        i > 0 && self.is_covered(i - 1, r) && self.is_covered(i, r)
+    }
+
+    // Whether the horizontal unit segment on line 'j' in column 'c' has covered cells on both
+    // sides.
+    fn h_interior(&self, j: usize, c: usize) -> bool {
+        j > 0 && self.is_covered(c, j - 1) && self.is_covered(c, j)
+    }
+
+    // Whether grid point (i, j) is a concave corner (with three of its four cells covered).
+    fn is_concave(&self, i: usize, j: usize) -> bool {
+        let cells = [(i.wrapping_sub(1), j.wrapping_sub(1)), (i, j.wrapping_sub(1)), (i.wrapping_sub(1), j), (i, j)];
+        cells.iter().filter(|&&(c, r)| self.is_covered(c, r)).count() == 3
+    }
+
+    // Whether any cut ends at, or passes through, grid point (i, j).
+    fn is_cut_at(&self, i: usize, j: usize) -> bool {
+        (j > 0 && self.vcuts[j - 1][i]) || (j < self.height() && self.vcuts[j][i])
+            || (i > 0 && self.hcuts[j][i - 1]) || (i < self.width() && self.hcuts[j][i])
+    }
+
+    fn into_rects(mut self) -> Vec<Rect<I>> {
+        let concave: Vec<(usize, usize)> = (0..=self.width()).cartesian_product(0..=self.height())
+            .filter(|&(i, j)| self.is_concave(i, j))
+            .collect();
+        // Chords join consecutive concave corners on the same line, if they pass through the
+        // interior. They cannot pass through any other point of the polygon.
+        let vchords: Vec<Chord> = concave.iter().tuple_windows()
+            .filter(|(a, b)| a.0 == b.0 && (a.1..b.1).all(|r| self.v_interior(a.0, r)))
+            .map(|(a, b)| (a.0, a.1, b.1))
+            .collect();
+        let hchords: Vec<Chord> = concave.iter().sorted_by_key(|&&(i, j)| (j, i)).tuple_windows()
+            .filter(|(a, b)| a.1 == b.1 && (a.0..b.0).all(|c| self.h_interior(a.1, c)))
+            .map(|(a, b)| (a.1, a.0, b.0))
+            .collect();
+        let (hchosen, vchosen) = max_independent_chords(&hchords, &vchords);
+        for (j, c_lo, c_hi) in hchosen {
+            (c_lo..c_hi).for_each(|c| self.hcuts[j][c] = true);
+        }
+        for (i, r_lo, r_hi) in vchosen {
+            (r_lo..r_hi).for_each(|r| self.vcuts[r][i] = true);
+        }
+
+        // Cut vertically from any remaining concave corners, away from the uncovered cell.
+        for &(i, j) in &concave {
+            if self.is_cut_at(i, j) {
+                continue;
+            }
+            let down = !self.is_covered(i - 1, j - 1) || !self.is_covered(i, j - 1);
+            let mut j = j;
+            loop {
+                let r = if down { j } else { j - 1 };
+                self.vcuts[r][i] = true;
+                j = if down { j + 1 } else { j - 1 };
+                let ahead = if down { j } else { j.wrapping_sub(1) };
+                if (i > 0 && self.hcuts[j][i - 1]) || (i < self.width() && self.hcuts[j][i])
+                    || !self.v_interior(i, ahead) || self.vcuts[ahead][i] {
+                    break;
+                }
+            }
+        }
+
+        // Every connected group of cells is now a rectangle.
+        let mut seen = vec![vec![false; self.width()]; self.height()];
+        let mut rects = vec![];
+        for (r, c) in (0..self.height()).cartesian_product(0..self.width()) {
+            if !self.covered[r][c] || seen[r][c] {
+                continue;
+            }
+            let (mut min, mut max) = ((c, r), (c, r));
+            let mut count = 0;
+            let mut stack = vec![(c, r)];
+            seen[r][c] = true;
+            while let Some((c, r)) = stack.pop() {
+                count += 1;
+                min = (min.0.min(c), min.1.min(r));
+                max = (max.0.max(c), max.1.max(r));
+                let mut neighbours = vec![];
+                if c > 0 && !self.vcuts[r][c] { neighbours.push((c - 1, r)); }
+                if c + 1 < self.width() && !self.vcuts[r][c + 1] { neighbours.push((c + 1, r)); }
+                if r > 0 && !self.hcuts[r][c] { neighbours.push((c, r - 1)); }
+                if r + 1 < self.height() && !self.hcuts[r + 1][c] { neighbours.push((c, r + 1)); }
+                for (nc, nr) in neighbours {
+                    if self.covered[nr][nc] && !seen[nr][nc] {
+                        seen[nr][nc] = true;
+                        stack.push((nc, nr));
+                    }
+                }
+            }
+            assert_eq!(count, (max.0 - min.0 + 1) * (max.1 - min.1 + 1), "Cut area is not a rectangle");
+            rects.push(Rect::from_corners(Point::new(self.xs[min.0], self.ys[min.1]), Point::new(self.xs[max.0 + 1], self.ys[max.1 + 1])));
+        }
+        rects
+    }
+}
+
+// Returns the largest set of horizontal and vertical chords where no two chords intersect
+// (including at their ends). Since only chords of different orientations can intersect, this is
+// the complement of a minimum vertex cover of their bipartite intersection graph, which (by König's
+// theorem) can be found from a maximum matching.
+fn max_independent_chords(hchords: &[Chord], vchords: &[Chord]) -> (Vec<Chord>, Vec<Chord>) {
+    let crosses = |&(j, c_lo, c_hi): &Chord, &(i, r_lo, r_hi): &Chord| c_lo <= i && i <= c_hi && r_lo <= j && j <= r_hi;
+    let adj: Vec<Vec<usize>> = hchords.iter()
+        .map(|h| (0..vchords.len()).filter(|&v| crosses(h, &vchords[v])).collect())
+        .collect();
+
+    // Kuhn's algorithm, finding an augmenting path from each horizontal chord in turn.
+    fn augment(h: usize, adj: &[Vec<usize>], seen: &mut [bool], matched: &mut [Option<usize>]) -> bool {
+        for &v in &adj[h] {
+            if !seen[v] {
+                seen[v] = true;
+                if matched[v].is_none_or(|h2| augment(h2, adj, seen, matched)) {
+                    matched[v] = Some(h);
+                    return true;
+                }
+            }
+        }
+        false
+    }
+    let mut matched: Vec<Option<usize>> = vec![None; vchords.len()];
+    for h in 0..hchords.len() {
+        augment(h, &adj, &mut vec![false; vchords.len()], &mut matched);
+    }
+
+    // Find the chords reachable by alternating paths from unmatched horizontal chords.
+    let mut h_reached = vec![false; hchords.len()];
+    let mut v_reached = vec![false; vchords.len()];
+    let mut stack: Vec<usize> = (0..hchords.len()).filter(|h| !matched.contains(&Some(*h))).collect();
+    stack.iter().for_each(|&h| h_reached[h] = true);
+    while let Some(h) = stack.pop() {
+        for &v in &adj[h] {
+            if !v_reached[v] {
+                v_reached[v] = true;
+                if let Some(h2) = matched[v].filter(|&h2| !h_reached[h2]) {
+                    h_reached[h2] = true;
+                    stack.push(h2);
+                }
+            }
+        }
+    }
+    // The minimum vertex cover is the unreached horizontal chords and reached vertical chords.
+    (hchords.iter().zip(h_reached).filter(|(_, r)| *r).map(|(c, _)| *c).collect(),
+     vchords.iter().zip(v_reached).filter(|(_, r)| !*r).map(|(c, _)| *c).collect())
+}
+
+// Whether an ordered list of spans covers the unit interval starting at 'x'.
+fn spans_cover<I: PrimInt>(spans: &[(I, I)], x: I) -> bool {
+    let i = spans.partition_point(|&(_, hi)| hi <= x);
+    i < spans.len() && spans[i].0 <= x
+}
+
+// Converts slabs (as returned by combine_slabs()) to the regions they cover.
+fn to_regions<I: PrimInt + Signed>(slabs: &[Slab<I>]) -> Vec<Region<I>> {
+    // The directed boundary edges of the slabs, keyed by start point, with the covered area on
+    // the right hand side. Since slabs may be split at any Y, there are redundant points between
+    // collinear vertical edges, but these are removed by 'RPolyBuilder'.
+    let mut edges: BTreeMap<Point<I>, Vec<Point<I>>> = BTreeMap::new();
+    let mut add = |start: Point<I>, end: Point<I>| edges.entry(start).or_default().push(end);
+    for (i, slab) in slabs.iter().enumerate() {
+        let (y_lo, y_hi) = (slab.y_lo, slab.y_hi);
+        for &(x_lo, x_hi) in &slab.spans {
+            add(Point::new(x_lo, y_hi), Point::new(x_lo, y_lo));
+            add(Point::new(x_hi, y_lo), Point::new(x_hi, y_hi));
+        }
+        // Horizontal edges are needed wherever the coverage above and below a line differs.
+        let above = if i > 0 && slabs[i - 1].y_hi == y_lo { &slabs[i - 1].spans[..] } else { &[] };
+        for (x_lo, x_hi) in combine_spans(above, &slab.spans, &|a, b| b && !a) {
+            add(Point::new(x_lo, y_lo), Point::new(x_hi, y_lo));
+        }
+        for (x_lo, x_hi) in combine_spans(above, &slab.spans, &|a, b| a && !b) {
+            add(Point::new(x_hi, y_lo), Point::new(x_lo, y_lo));
+        }
+        if i + 1 == slabs.len() || slabs[i + 1].y_lo != y_hi {
+            for &(x_lo, x_hi) in &slab.spans {
+                add(Point::new(x_hi, y_hi), Point::new(x_lo, y_hi));
+            }
+        }
+    }
+
+    let mut outers: Vec<RPoly<I>> = vec![];
+    let mut holes: Vec<RPoly<I>> = vec![];
+    while let Some(&start) = edges.keys().next() {
+        for path in split_loops(trace_boundary(&mut edges, start)) {
+            let mut builder = RPolyBuilder::new();
+            path.iter().for_each(|p| builder.add_point(p));
+            let poly = builder.try_close().expect("Region boundaries are always valid polygons");
+            if Polygon::from(&poly).winding() == Some(Winding::Clockwise) {
+                outers.push(poly);
+            } else {
+                holes.push(poly);
+            }
+        }
+    }
+    // The edges and area of each outer boundary, which are needed for every hole.
+    let outer_edges: Vec<Vec<Edge<I>>> = outers.iter().map(|o| o.get_orthogonal_edges()).collect();
+    let outer_areas: Vec<i128> = outer_edges.iter().map(|e| RPoly::get_edge_aligned_area(e)).collect();
+    let mut outer_holes: Vec<Vec<RPoly<I>>> = outers.iter().map(|_| vec![]).collect();
+    for hole in holes {
+        // Regions can be nested inside the holes of other regions, so a hole belongs to the
+        // smallest region which contains it. Boundaries never cross, so any hole vertex which is
+        // not on an outer boundary (where the two may touch at a corner) decides which side of it
+        // the whole hole is on.
+        let contains = |edges: &[Edge<I>]| hole.points().iter()
+            .map(|p| RPoly::classify_with_edges(edges, p))
+            .find(|&loc| loc != Location::Boundary) == Some(Location::Inside);
+        let i = (0..outers.len())
+            .filter(|&i| contains(&outer_edges[i]))
+            .min_by_key(|&i| outer_areas[i])
+            .expect("Every hole is inside a region");
+        outer_holes[i].push(hole);
+    }
+    outers.into_iter().zip(outer_holes).map(|(outer, holes)| Region { outer, holes }).collect()
+}
+
+// Follows (and removes) directed edges from the given start point until it is reached again,
+// returning the points visited. Where there is a choice of edges (at a corner where two covered
+// areas touch diagonally), the tightest turn around the covered area is taken.
+fn trace_boundary<I: PrimInt + Signed>(edges: &mut BTreeMap<Point<I>, Vec<Point<I>>>, start: Point<I>) -> Vec<Point<I>> {
+    let dir = |a: &Point<I>, b: &Point<I>| Edge { start: a, end: b }.direction();
+    let mut path = vec![];
+    let mut cur = start;
+    let mut d_in: Option<Dir> = None;
+    loop {
+        path.push(cur);
+        let ends = edges.get_mut(&cur).expect("Region boundaries are always closed");
+        let i = match d_in {
+            Some(d) => [d.turn_right(), d, d.turn_left()].iter()
+                .find_map(|&turn| ends.iter().position(|e| dir(&cur, e) == turn))
+                .expect("Region boundaries never reverse"),
+            None => 0,
+        };
+        let next = ends.swap_remove(i);
+        if ends.is_empty() {
+            edges.remove(&cur);
+        }
+        d_in = Some(dir(&cur, &next));
+        cur = next;
+        if cur == start {
+            return path;
+        }
+    }
+}
+
+// Splits a closed path which visits the same point more than once into simple closed paths.
+fn split_loops<I: PrimInt + Signed>(path: Vec<Point<I>>) -> Vec<Vec<Point<I>>> {
+    let mut loops = vec![];
+    let mut stack: Vec<Point<I>> = vec![];
+    let mut index: BTreeMap<Point<I>, usize> = BTreeMap::new();
+    for p in path {
+        if let Some(&i) = index.get(&p) {
+            let inner = stack.split_off(i);
+            inner.iter().for_each(|q| { index.remove(q); });
+            loops.push(inner);
+        }
+        index.insert(p, stack.len());
+        stack.push(p);
+    }
+    loops.push(stack);
+    loops
 }
 
 fn next<I>(vec: &Vec<I>, mut i: usize) -> (&I, usize) {
@@ -257,7 +1029,12 @@ fn to_cyclic_pairs<I>(items: &Vec<I>) -> TupleWindows<Chain<Iter<I>, Iter<I>>, (
     items[items.len() - 1..].iter().chain(items.iter()).tuple_windows::<(&I, &I)>()
 }
 
-fn insert_or_remove(active: &mut Vec<(i32, usize)>, pos: (i32, usize)) -> bool {
+// Widens a coordinate so that differences and products of coordinates cannot overflow.
+pub(crate) fn wide<I: PrimInt>(v: I) -> i128 {
+    v.to_i128().unwrap()
+}
+
+fn insert_or_remove<I: PrimInt>(active: &mut Vec<(I, usize)>, pos: (I, usize)) -> bool {
     match active.binary_search(&pos) {
         Err(i) => {
             active.insert(i, pos);
@@ -271,19 +1048,22 @@ fn insert_or_remove(active: &mut Vec<(i32, usize)>, pos: (i32, usize)) -> bool {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
+    use indoc::indoc;
+
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
 
-    #[test]
-    fn test_poly() {
-        /*
-         *     7--6   3--2
-         *     |  |   |  |
-         * Y   |  5---4  |
-         * ^   |         |
-         * >X  0---------1
-         */
+    /// A "U" shape with a notch in the middle of one side, used by several tests.
+    ///
+    /// ```text
+    ///     7--6   3--2
+    ///     |  |   |  |
+    /// Y   |  5---4  |
+    /// ^   |         |
+    /// >X  0---------1
+    /// ```
+    pub(crate) fn u_shape() -> RPoly<i32> {
         let mut poly = RPoly::new();
         poly.add_relative(Right, 10);
         poly.add_relative(Down, 10);
@@ -293,9 +1073,305 @@ mod tests {
         poly.add_relative(Down, 5);
         poly.add_relative(Left, 3);
         poly.add_relative(Up, 10);
+        poly
+    }
+
+    #[test]
+    fn test_poly() {
+        let poly = u_shape();
         assert_eq!(poly.get_perimeter(), 50);
         assert_eq!(poly.get_area(), 80);
         assert_eq!(poly.get_external_area(), 106);
         assert_eq!(poly.get_internal_area(), 56);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_large_poly() {
+        // An area well beyond the range of i64 (and coordinate differences beyond i32).
+        let n: i64 = 1 << 40;
+        let mut poly = RPoly::new();
+        poly.add_relative(Right, n);
+        poly.add_relative(Down, n);
+        poly.add_relative(Left, n);
+        poly.add_relative(Up, n);
+        assert_eq!(poly.get_perimeter(), 4 * (n as i128));
+        assert_eq!(poly.get_area(), (n as i128) * (n as i128));
+        assert_eq!(poly.get_external_area(), (n as i128 + 1) * (n as i128 + 1));
+    }
+
+    #[test]
+    fn test_validate() {
+        let poly = |points: &[(i32, i32)]| RPoly { poly: points.iter().map(|&p| Point::from(p)).collect() };
+        assert_eq!(poly(&[(0, 0), (2, 0), (2, 2)]).validate(), Err(PolyError::TooFewPoints(3)));
+        assert_eq!(poly(&[(0, 0), (2, 0), (2, 2), (0, 2)]).validate(), Ok(()));
+        assert_eq!(poly(&[(0, 0), (2, 0), (3, 2), (0, 2)]).validate(), Err(PolyError::DiagonalEdge { index: 1 }));
+        assert_eq!(poly(&[(0, 0), (2, 0), (2, 0), (2, 2), (0, 2)]).validate(), Err(PolyError::ZeroLengthEdge { index: 1 }));
+        assert_eq!(poly(&[(0, 0), (1, 0), (2, 0), (2, 2), (0, 2)]).validate(), Err(PolyError::CollinearEdges { index: 0 }));
+        assert_eq!(poly(&[(0, 0), (2, 0), (2, 2), (1, 2), (0, 2)]).validate(), Err(PolyError::CollinearEdges { index: 2 }));
+        assert_eq!(poly(&[(0, 1), (0, 0), (2, 0), (2, 2), (1, 2)]).validate(), Err(PolyError::NotClosed));
+        assert_eq!(poly(&[(0, 0), (2, 0), (2, 2), (0, 2), (0, 1)]).validate(), Err(PolyError::CollinearEdges { index: 3 }));
+
+        // A loop which crosses itself, where edge 3 (the leftmost vertical edge in the middle)
+        // crosses edge 6 before edge 6 crosses edge 1.
+        let crossing = poly(&[(0, 0), (2, 0), (2, 3), (1, 3), (1, 1), (3, 1), (3, 2), (0, 2)]);
+        assert_eq!(crossing.validate(), Err(PolyError::SelfIntersecting { first: 3, second: 6 }));
+        // Only the structural checks are repeated by other operations.
+        assert_eq!(crossing.validate_structure(), Ok(()));
+        assert_eq!(crossing.get_perimeter(), 16);
+        // Two squares which touch at (2, 2), where edges 2 and 6 are on the same line.
+        let touching = poly(&[(0, 0), (2, 0), (2, 2), (4, 2), (4, 4), (2, 4), (2, 2), (0, 2)]);
+        assert_eq!(touching.validate(), Err(PolyError::SelfIntersecting { first: 2, second: 6 }));
+        // Vertical edges 1 and 5 overlap, but the first intersection found is where edge 4 meets
+        // edge 1, since that is checked as soon as edge 1 is seen.
+        let overlapping = poly(&[(0, 0), (3, 0), (3, 4), (5, 4), (5, 1), (3, 1), (3, 3), (0, 3)]);
+        assert_eq!(overlapping.validate(), Err(PolyError::SelfIntersecting { first: 1, second: 4 }));
+    }
+
+    #[test]
+    fn test_builder() {
+        // A 3x2 rectangle, described with redundant moves and closing back onto the start point.
+        let mut builder = RPolyBuilder::new();
+        builder.add_relative(Down, 1);
+        builder.add_relative(Right, 2);
+        builder.add_relative(Right, 0);
+        builder.add_relative(Right, 2);
+        builder.add_relative(Left, 1);
+        builder.add_relative(Up, 2);
+        builder.add_relative(Left, 3);
+        builder.add_relative(Down, 1);
+        let poly = builder.try_close().unwrap();
+        assert_eq!(poly.poly.len(), 4);
+        assert_eq!(poly.get_area(), 6);
+
+        let mut builder = RPolyBuilder::new();
+        builder.add_xy(0, 0);
+        builder.add_xy(3, 3);
+        builder.add_xy(0, 3);
+        assert_eq!(builder.try_close().err(), Some(PolyError::TooFewPoints(3)));
+    }
+
+    #[test]
+    fn test_classify() {
+        // The "U" shape, with Y pointing down.
+        let poly = u_shape();
+        assert_eq!(poly.classify(&Point::new(1, 1)), Location::Inside);
+        assert_eq!(poly.classify(&Point::new(5, 5)), Location::Boundary);
+        assert_eq!(poly.classify(&Point::new(0, 7)), Location::Boundary);
+        assert_eq!(poly.classify(&Point::new(5, 7)), Location::Outside);
+        assert_eq!(poly.classify(&Point::new(11, 0)), Location::Outside);
+        // A ray along the top of the "notch" passes through two vertices.
+        assert_eq!(poly.classify(&Point::new(2, 5)), Location::Inside);
+        assert_eq!(poly.classify(&Point::new(-1, 5)), Location::Outside);
+
+        let rect = Rect::from_corners(Point::new(-2, -2), Point::new(12, 12));
+        let classes = poly.classify_rect(&rect);
+        for (p, &c) in rect.points().zip(classes.iter()) {
+            assert_eq!(c, poly.classify(&p), "{:?}", p);
+        }
+        let count = |loc| classes.iter().filter(|&&c| c == loc).count() as i128;
+        assert_eq!(count(Location::Inside), poly.get_internal_area());
+        assert_eq!(count(Location::Boundary), poly.get_perimeter());
+
+        // Clipping to a rectangle which only partly overlaps the polygon.
+        let rect = Rect::from_corners(Point::new(5, 3), Point::new(20, 8));
+        for (p, &c) in rect.points().zip(poly.classify_rect(&rect).iter()) {
+            assert_eq!(c, poly.classify(&p), "{:?}", p);
+        }
+    }
+
+    #[test]
+    fn test_rasterise() {
+        // A backwards "C" shape, with Y pointing down.
+        let mut poly = RPoly::new();
+        poly.add_relative(Right, 6);
+        poly.add_relative(Down, 8);
+        poly.add_relative(Left, 6);
+        poly.add_relative(Up, 4);
+        poly.add_relative(Right, 4);
+        poly.add_relative(Up, 2);
+        poly.add_relative(Left, 4);
+        poly.add_relative(Up, 2);
+        let grid = poly.to_grid(|loc| match loc {
+            Location::Inside => 'o',
+            Location::Boundary => '#',
+            Location::Outside => '.',
+        });
+        let expected = AGrid::from_lines(indoc! {"
+            #######
+            #ooooo#
+            #####o#
+            ....#o#
+            #####o#
+            #ooooo#
+            #ooooo#
+            #ooooo#
+            #######"
+        });
+        assert_eq!(grid, expected);
+    }
+
+    #[test]
+    fn test_mask_brute_force() {
+        // The "U" shape, with Y pointing down.
+        let poly = u_shape();
+        let boundary = poly.to_mask(|loc| loc == Location::Boundary);
+        let internal = poly.to_mask(|loc| loc == Location::Inside);
+        let external = poly.to_mask(|loc| loc != Location::Outside);
+        assert_eq!(boundary.count_ones() as i128, poly.get_perimeter());
+        assert_eq!(internal.count_ones() as i128, poly.get_internal_area());
+        assert_eq!(external.count_ones() as i128, poly.get_external_area());
+
+        // Flood fill the outside (with a margin of one point) from the corner, without crossing
+        // the boundary. Everything not reached is part of the external area.
+        let (w, h) = (boundary.width() as i32, boundary.height() as i32);
+        let mut outside = vec![Point::new(-1, -1)];
+        let mut seen = vec![];
+        while let Some(p) = outside.pop() {
+            if p.x < -1 || p.y < -1 || p.x > w || p.y > h || seen.contains(&p) {
+                continue;
+            }
+            let on_boundary = p.x >= 0 && p.y >= 0 && p.x < w && p.y < h
+                && boundary.get_xy(p.x as usize, p.y as usize);
+            if !on_boundary {
+                seen.push(p);
+                outside.extend(Dir::all().map(|d| p.move_by(1, d)));
+            }
+        }
+        let reached = seen.iter().filter(|p| p.x >= 0 && p.y >= 0 && p.x < w && p.y < h).count();
+        assert_eq!((w * h) as usize - reached, external.count_ones() as usize);
+    }
+
+    fn rect(x_lo: i32, y_lo: i32, x_hi: i32, y_hi: i32) -> RPoly<i32> {
+        RPoly { poly: vec![Point::new(x_lo, y_lo), Point::new(x_hi, y_lo), Point::new(x_hi, y_hi), Point::new(x_lo, y_hi)] }
+    }
+
+    // Checks the regions against the operation applied to every unit square near the polygons,
+    // where a unit square is inside a polygon if its center is.
+    fn check_regions<F: Fn(bool, bool) -> bool>(lhs: &RPoly<i32>, rhs: &RPoly<i32>, regions: &[Region<i32>], op: F) {
+        let doubled = |poly: &RPoly<i32>| RPoly { poly: poly.poly.iter().map(|p| Point::new(2 * p.x, 2 * p.y)).collect() };
+        let inside = |poly: &RPoly<i32>, x: i32, y: i32| doubled(poly).classify(&Point::new(2 * x + 1, 2 * y + 1)) == Location::Inside;
+        let bounds = lhs.bounds().union(&rhs.bounds());
+        let (min, max) = (bounds.top_left(), bounds.bottom_right());
+        for (x, y) in (min.x - 1..=max.x).cartesian_product(min.y - 1..=max.y) {
+            let in_regions = regions.iter()
+                .filter(|r| inside(&r.outer, x, y) && !r.holes.iter().any(|h| inside(h, x, y)))
+                .count();
+            assert_eq!(in_regions, op(inside(lhs, x, y), inside(rhs, x, y)) as usize, "({}, {})", x, y);
+        }
+    }
+
+    #[test]
+    fn test_boolean_ops() {
+        let (a, b) = (rect(0, 0, 4, 4), rect(2, 2, 6, 6));
+        let union = a.union(&b);
+        assert_eq!(union.len(), 1);
+        assert_eq!(union[0].outer.points().len(), 8);
+        assert_eq!(union[0].get_area(), 28);
+        check_regions(&a, &b, &union, |a, b| a || b);
+        let intersection = a.intersection(&b);
+        assert_eq!(intersection[0].get_area(), 4);
+        check_regions(&a, &b, &intersection, |a, b| a && b);
+        let difference = a.difference(&b);
+        assert_eq!(difference[0].get_area(), 12);
+        check_regions(&a, &b, &difference, |a, b| a && !b);
+
+        // Cutting a hole in a square.
+        let (a, b) = (rect(0, 0, 6, 6), rect(2, 2, 4, 4));
+        let difference = a.difference(&b);
+        assert_eq!(difference.len(), 1);
+        assert_eq!(difference[0].holes.len(), 1);
+        assert_eq!(difference[0].get_area(), 32);
+        check_regions(&a, &b, &difference, |a, b| a && !b);
+        assert!(b.difference(&a).is_empty());
+
+        // Cutting a rectangle in two.
+        let (a, b) = (rect(0, 0, 6, 2), rect(2, -1, 4, 3));
+        let difference = a.difference(&b);
+        assert_eq!(difference.len(), 2);
+        check_regions(&a, &b, &difference, |a, b| a && !b);
+
+        // Squares sharing an edge are joined, but squares sharing a corner are not.
+        assert_eq!(rect(0, 0, 2, 2).union(&rect(2, 0, 4, 2))[0].outer.points().len(), 4);
+        let (a, b) = (rect(0, 0, 2, 2), rect(2, 2, 4, 4));
+        let union = a.union(&b);
+        assert_eq!(union.len(), 2);
+        check_regions(&a, &b, &union, |a, b| a || b);
+        assert!(a.intersection(&b).is_empty());
+    }
+
+    #[test]
+    fn test_boolean_ops_touching_hole() {
+        // A "C" shape, with a square filling the top of its opening. This leaves a hole which
+        // touches the outside of the region at (2, 2).
+        let c = RPoly { poly: [(0, 0), (3, 0), (3, 1), (1, 1), (1, 2), (2, 2), (2, 3), (0, 3)].map(Point::from).to_vec() };
+        let square = rect(2, 1, 3, 2);
+        let union = c.union(&square);
+        assert_eq!(union.len(), 1);
+        assert_eq!(union[0].holes.len(), 1);
+        assert_eq!(union[0].holes[0].get_area(), 1);
+        assert_eq!(union[0].get_area(), 7);
+        check_regions(&c, &square, &union, |a, b| a || b);
+    }
+
+    // Checks that the rectangles have the same total area as the polygon, and don't overlap.
+    fn check_rects(poly: &RPoly<i32>, rects: &[Rect<i32>]) {
+        let area = |r: &Rect<i32>| ((r.width() - 1) * (r.height() - 1)) as i128;
+        assert_eq!(rects.iter().map(area).sum::<i128>(), poly.get_area());
+        for (a, b) in rects.iter().tuple_combinations() {
+            assert!(a.intersection(b).is_none_or(|r| r.width() == 1 || r.height() == 1), "{:?} {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_rects() {
+        // A tall rectangle with a small square on its right side, which needs three horizontal
+        // strips, but only two rectangles.
+        let tee = RPoly { poly: [(0, 0), (2, 0), (2, 1), (3, 1), (3, 2), (2, 2), (2, 3), (0, 3)].map(Point::from).to_vec() };
+        let rects = tee.to_rects();
+        assert_eq!(rects.len(), 3);
+        check_rects(&tee, &rects);
+        let rects = tee.to_min_rects();
+        assert_eq!(rects, vec![
+            Rect::from_corners(Point::new(0, 0), Point::new(2, 3)),
+            Rect::from_corners(Point::new(2, 1), Point::new(3, 2)),
+        ]);
+
+        // The "U" shape, where strips which continue downwards are joined.
+        let poly = u_shape();
+        assert_eq!(poly.to_rects().len(), 3);
+        check_rects(&poly, &poly.to_rects());
+        assert_eq!(poly.to_min_rects().len(), 3);
+        check_rects(&poly, &poly.to_min_rects());
+    }
+
+    #[test]
+    fn test_min_rects() {
+        // A "plus" shape, where all four concave corners are joined by chords, but only two
+        // chords can be used without crossing.
+        let plus = RPoly { poly: [(1, 0), (2, 0), (2, 1), (3, 1), (3, 2), (2, 2), (2, 3), (1, 3), (1, 2), (0, 2), (0, 1), (1, 1)].map(Point::from).to_vec() };
+        assert_eq!(plus.to_min_rects().len(), 3);
+        check_rects(&plus, &plus.to_min_rects());
+
+        // A staircase, with concave corners which are not joined by chords.
+        let mut stairs = RPolyBuilder::new();
+        stairs.add_xy(0, 0);
+        for _ in 0..4 {
+            stairs.add_relative(Right, 2);
+            stairs.add_relative(Down, 1);
+        }
+        stairs.add_relative(Left, 8);
+        let stairs = stairs.try_close().unwrap();
+        assert_eq!(stairs.to_min_rects().len(), 4);
+        check_rects(&stairs, &stairs.to_min_rects());
+
+        // A square with a notch in each side, where the 8 concave corners are joined by two
+        // horizontal and two vertical chords, but only two of these can be used without crossing.
+        let notched = RPoly { poly: [
+            (0, 0), (2, 0), (2, 1), (4, 1), (4, 0), (6, 0), (6, 2), (5, 2), (5, 4), (6, 4), (6, 6),
+            (4, 6), (4, 5), (2, 5), (2, 6), (0, 6), (0, 4), (1, 4), (1, 2), (0, 2),
+        ].map(Point::from).to_vec() };
+        let rects = notched.to_min_rects();
+        assert_eq!(rects.len(), 8 - 2 + 1);
+        check_rects(&notched, &rects);
+    }
+}
@@ -0,0 +1,244 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use num_traits::{PrimInt, Signed};
+
+use crate::polygon::Polygon;
+use crate::rpoly::{Region, RPoly};
+use crate::xy::{Dir, Point};
+
+/// Options for drawing an `Svg` image.
+#[derive(Clone, Copy, Debug)]
+pub struct SvgOptions {
+    /// The size of one unit of the X/Y plane in the image.
+    pub scale: f64,
+    /// If set, grid lines are drawn at every integer coordinate covered by the image. This makes
+    /// it easy to see which points are counted by the internal and external areas of an `RPoly`,
+    /// but should not be used for very large polygons.
+    pub grid: bool,
+    /// If set, each polygon vertex is labelled with its index.
+    pub vertex_indices: bool,
+    /// If set, each horizontal or vertical polygon edge is labelled with its direction.
+    pub edge_dirs: bool,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions { scale: 20.0, grid: false, vertex_indices: true, edge_dirs: true }
+    }
+}
+
+/// An SVG image of polygons and points in the X/Y plane, with (0, 0) at the top left (as for
+/// `xy::Dir`). The image is sized to fit everything added to it, and is written out via `Display`.
+///
+/// Colours are any SVG colour (e.g. `"red"` or `"#ff0000"`), and are escaped before being written
+/// into attributes.
+#[derive(Clone, Debug)]
+pub struct Svg {
+    options: SvgOptions,
+    shapes: Vec<Shape>,
+}
+
+#[derive(Clone, Debug)]
+enum Shape {
+    // A filled area bounded by one or more closed rings of points (using the "even-odd" rule, so
+    // rings inside other rings are holes).
+    Area { rings: Vec<Vec<(f64, f64)>>, colour: String },
+    Points { points: Vec<(f64, f64)>, colour: String },
+}
+
+#[allow(unused)]
+impl Svg {
+    pub fn new(options: SvgOptions) -> Svg { Svg { options, shapes: vec![] } }
+
+    pub fn add_rpoly<I: PrimInt + Signed>(&mut self, poly: &RPoly<I>, colour: &str) {
+        self.add_area(vec![to_coords(poly.points())], colour);
+    }
+
+    /// Adds a region (e.g. from `RPoly::union()`), showing its holes as unfilled areas.
+    pub fn add_region<I: PrimInt + Signed>(&mut self, region: &Region<I>, colour: &str) {
+        let rings = Some(&region.outer).into_iter().chain(region.holes.iter())
+            .map(|p| to_coords(p.points()))
+            .collect();
+        self.add_area(rings, colour);
+    }
+
+    pub fn add_polygon<I: PrimInt + Signed>(&mut self, poly: &Polygon<I>, colour: &str) {
+        self.add_area(vec![to_coords(poly.points())], colour);
+    }
+
+    /// Adds a set of points, drawn as dots without labels.
+    pub fn add_points<'a, I: PrimInt + 'a, It: IntoIterator<Item=&'a Point<I>>>(&mut self, points: It, colour: &str) {
+        let points: Vec<Point<I>> = points.into_iter().copied().collect();
+        self.shapes.push(Shape::Points { points: to_coords(&points), colour: escape(colour) });
+    }
+
+    fn add_area(&mut self, rings: Vec<Vec<(f64, f64)>>, colour: &str) {
+        self.shapes.push(Shape::Area { rings, colour: escape(colour) });
+    }
+
+    // The minimum and maximum coordinates of everything in the image, expanded by one unit on
+    // every side (so that points on the edge are not cut off).
+    fn bounds(&self) -> ((f64, f64), (f64, f64)) {
+        let all = self.shapes.iter().flat_map(|s| match s {
+            Shape::Area { rings, .. } => rings.iter().flatten().collect::<Vec<_>>(),
+            Shape::Points { points, .. } => points.iter().collect(),
+        });
+        let empty = ((f64::INFINITY, f64::INFINITY), (f64::NEG_INFINITY, f64::NEG_INFINITY));
+        let (min, max) = all.fold(empty, |(min, max), &(x, y)| {
+            ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y)))
+        });
+        if min.0 > max.0 {
+            // An empty image is centered on the origin.
+            return ((-1.0, -1.0), (1.0, 1.0));
+        }
+        ((min.0 - 1.0, min.1 - 1.0), (max.0 + 1.0, max.1 + 1.0))
+    }
+
+    fn write_grid(&self, out: &mut Formatter<'_>, min: (f64, f64), max: (f64, f64)) -> fmt::Result {
+        let s = self.options.scale;
+        for x in (min.0.ceil() as i64)..=(max.0.floor() as i64) {
+            let x = x as f64 * s;
+            writeln!(out, r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="lightgrey"/>"#, x, min.1 * s, x, max.1 * s)?;
+        }
+        for y in (min.1.ceil() as i64)..=(max.1.floor() as i64) {
+            let y = y as f64 * s;
+            writeln!(out, r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="lightgrey"/>"#, min.0 * s, y, max.0 * s, y)?;
+        }
+        Ok(())
+    }
+
+    fn write_area(&self, out: &mut Formatter<'_>, rings: &[Vec<(f64, f64)>], colour: &str) -> fmt::Result {
+        let s = self.options.scale;
+        let path: String = rings.iter()
+            .map(|ring| {
+                let steps: Vec<String> = ring.iter().map(|(x, y)| format!("{} {}", x * s, y * s)).collect();
+                format!("M {} Z", steps.join(" L "))
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(out, r#"<path d="{}" fill="{}" fill-opacity="0.2" fill-rule="evenodd" stroke="{}"/>"#, path, colour, colour)?;
+        for ring in rings {
+            for (i, &(x, y)) in ring.iter().enumerate() {
+                let (nx, ny) = ring[(i + 1) % ring.len()];
+                if let Some(dir) = self.options.edge_dirs.then(|| edge_dir((x, y), (nx, ny))).flatten() {
+                    // An arrow in the middle of the edge, pointing along it, and its direction.
+                    let (mx, my) = ((x + nx) / 2.0 * s, (y + ny) / 2.0 * s);
+                    writeln!(out, r#"<polyline points="{},{} {},{} {},{}" fill="none" stroke="{}" marker-mid="url(#arrow)"/>"#,
+                             x * s, y * s, mx, my, nx * s, ny * s, colour)?;
+                    writeln!(out, r#"<text x="{}" y="{}" class="dir">{}</text>"#, mx, my, dir)?;
+                }
+                if self.options.vertex_indices {
+                    writeln!(out, r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#, x * s, y * s, s * 0.1, colour)?;
+                    writeln!(out, r#"<text x="{}" y="{}" class="index">{}</text>"#, x * s, y * s, i)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_points(&self, out: &mut Formatter<'_>, points: &[(f64, f64)], colour: &str) -> fmt::Result {
+        let s = self.options.scale;
+        for (x, y) in points {
+            writeln!(out, r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#, x * s, y * s, s * 0.2, colour)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Svg {
+    fn fmt(&self, out: &mut Formatter<'_>) -> fmt::Result {
+        let s = self.options.scale;
+        let (min, max) = self.bounds();
+        writeln!(out, r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+                 min.0 * s, min.1 * s, (max.0 - min.0) * s, (max.1 - min.1) * s)?;
+        writeln!(out, "<defs>")?;
+        writeln!(out, r#"<marker id="arrow" viewBox="0 0 10 10" refX="5" refY="5" markerWidth="6" markerHeight="6" orient="auto"><path d="M 0 0 L 10 5 L 0 10 Z"/></marker>"#)?;
+        writeln!(out, "<style>text {{ font-family: sans-serif; font-size: {}px; }} .dir {{ text-anchor: middle; dominant-baseline: hanging; }} .index {{ dominant-baseline: text-after-edge; }}</style>", s * 0.4)?;
+        writeln!(out, "</defs>")?;
+        if self.options.grid {
+            self.write_grid(out, min, max)?;
+        }
+        for shape in &self.shapes {
+            match shape {
+                Shape::Area { rings, colour } => self.write_area(out, rings, colour)?,
+                Shape::Points { points, colour } => self.write_points(out, points, colour)?,
+            }
+        }
+        writeln!(out, "</svg>")
+    }
+}
+
+// Escapes text for use in an attribute value (or element content).
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;")
+}
+
+fn to_coords<I: PrimInt>(points: &[Point<I>]) -> Vec<(f64, f64)> {
+    points.iter().map(|p| (p.x.to_f64().unwrap(), p.y.to_f64().unwrap())).collect()
+}
+
+// The direction from one point to another, if they are on the same horizontal or vertical line.
+fn edge_dir(from: (f64, f64), to: (f64, f64)) -> Option<Dir> {
+    match (to.0 - from.0, to.1 - from.1) {
+        (dx, dy) if dy == 0.0 && dx > 0.0 => Some(Dir::Right),
+        (dx, dy) if dy == 0.0 && dx < 0.0 => Some(Dir::Left),
+        (dx, dy) if dx == 0.0 && dy > 0.0 => Some(Dir::Down),
+        (dx, dy) if dx == 0.0 && dy < 0.0 => Some(Dir::Up),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::xy::Dir::{Down, Left, Right, Up};
+
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    #[test]
+    fn test_rpoly_svg() {
+        let mut poly: RPoly<i32> = RPoly::new();
+        poly.add_relative(Right, 2);
+        poly.add_relative(Down, 1);
+        poly.add_relative(Left, 2);
+        poly.add_relative(Up, 1);
+        let mut svg = Svg::new(SvgOptions { scale: 10.0, grid: true, ..SvgOptions::default() });
+        svg.add_rpoly(&poly, "blue");
+        svg.add_points(&[Point::new(1, 0)], "red");
+        let out = svg.to_string();
+        assert!(out.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-10 -10 40 30">"#));
+        assert!(out.contains(r#"<path d="M 20 0 L 20 10 L 0 10 L 0 0 Z" fill="blue""#));
+        // Grid lines for X from -1 to 3, and Y from -1 to 2.
+        assert_eq!(out.matches("<line").count(), 5 + 4);
+        for dir in Dir::all() {
+            assert!(out.contains(&format!(">{}</text>", dir)));
+        }
+        assert!(out.contains(r#"class="index">3</text>"#));
+        // Four polygon vertices, and one separate point.
+        assert_eq!(out.matches("<circle").count(), 5);
+    }
+
+    #[test]
+    fn test_polygon_svg() {
+        let mut poly: Polygon<i64> = Polygon::new();
+        poly.add_xy(0, 0);
+        poly.add_xy(4, 2);
+        poly.add_xy(0, 2);
+        let mut svg = Svg::new(SvgOptions { vertex_indices: false, ..SvgOptions::default() });
+        svg.add_polygon(&poly, "green");
+        let out = svg.to_string();
+        // Only the horizontal and vertical edges are labelled.
+        assert_eq!(out.matches("<polyline").count(), 2);
+        assert_eq!(out.matches("<circle").count(), 0);
+        assert_eq!(out.matches("<line").count(), 0);
+    }
+
+    #[test]
+    fn test_escaped_colour() {
+        let mut svg = Svg::new(SvgOptions::default());
+        svg.add_points(&[Point::new(0, 0)], r#"red" onload="<&>"#);
+        let out = svg.to_string();
+        assert!(out.contains(r#"fill="red&quot; onload=&quot;&lt;&amp;>""#));
+    }
+}
@@ -0,0 +1,163 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use crate::agrid::AGrid;
+use crate::rpoly::{RPoly, RPolyBuilder};
+use crate::xy::{Dir, Point};
+
+/// A closed loop of connected tiles found on a grid.
+#[derive(Debug)]
+pub struct Trace {
+    /// The polygon passing through the center of every tile in the loop, with a point for each
+    /// tile where the loop turns a corner.
+    pub poly: RPoly<i64>,
+    /// The directions in which the start tile connects to the rest of the loop, which are inferred
+    /// from its neighbours rather than from the character at the start position.
+    #[allow(unused)]
+    pub start: (Dir, Dir),
+}
+
+/// Follows a loop of connected tiles on a grid, starting from (and finally returning to) the given
+/// start point.
+///
+/// The `connections` function returns the pair of directions joined by a tile (e.g. `(Up, Down)`
+/// for a vertical pipe), or `None` if the tile cannot be part of a loop. Moving between two tiles
+/// requires both tiles to be connected to each other.
+///
+/// The character at the start point is ignored. Instead, each direction out of the start point is
+/// tried in turn (in the order of `Dir::all()`) until one leads back to the start. If no loop is
+/// found, the error describes where the first attempt broke off.
+pub fn trace_loop<F: Fn(char) -> Option<(Dir, Dir)>>(grid: &AGrid, start: &Point<usize>, connections: F) -> Result<Trace, TraceError> {
+    let mut first_err = None;
+    for d_start in Dir::all() {
+        // Most directions out of the start point are expected to lead nowhere, so only report a
+        // break in the loop if the first step was connected.
+        let from = d_start.reverse();
+        let connected = step(grid, start, d_start)
+            .and_then(|n| connections(grid.get(&n)))
+            .is_some_and(|(a, b)| a == from || b == from);
+        if connected {
+            match follow(grid, start, d_start, &connections) {
+                Ok(trace) => return Ok(trace),
+                Err(e) => { first_err.get_or_insert(e); }
+            }
+        }
+    }
+    Err(first_err.unwrap_or(TraceError::NoConnections { pos: *start }))
+}
+
+fn follow<F: Fn(char) -> Option<(Dir, Dir)>>(grid: &AGrid, start: &Point<usize>, d_start: Dir, connections: &F) -> Result<Trace, TraceError> {
+    // The builder removes the redundant points along straight runs of tiles.
+    let mut builder = RPolyBuilder::new();
+    builder.add_point(&start.cast());
+    let (mut pos, mut d) = (*start, d_start);
+    loop {
+        let next = step(grid, &pos, d).ok_or(TraceError::OutOfBounds { pos, dir: d })?;
+        if next == *start {
+            let poly = builder.try_close().expect("A traced loop is always a valid polygon");
+            return Ok(Trace { poly, start: (d_start, d.reverse()) });
+        }
+        // We arrive from the opposite side to the direction of travel, and leave by the other side.
+        let from = d.reverse();
+        d = match connections(grid.get(&next)) {
+            Some((a, b)) if a == from => b,
+            Some((a, b)) if b == from => a,
+            _ => return Err(TraceError::Disconnected { pos: next, dir: d }),
+        };
+        pos = next;
+        builder.add_point(&pos.cast());
+    }
+}
+
+// Returns the adjacent point in the given direction, if it is within the grid.
+fn step(grid: &AGrid, p: &Point<usize>, d: Dir) -> Option<Point<usize>> {
+    p.checked_move_by(1, d).filter(|p| grid.bounds().contains(p))
+}
+
+/// The reasons why a loop cannot be traced on a grid.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TraceError {
+    /// None of the start point's neighbours are connected to it.
+    NoConnections { pos: Point<usize> },
+    /// The tile at the given point is connected to the edge of the grid, in the given direction.
+    OutOfBounds { pos: Point<usize>, dir: Dir },
+    /// The tile at the given point cannot be entered when travelling in the given direction.
+    Disconnected { pos: Point<usize>, dir: Dir },
+}
+
+impl Display for TraceError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceError::NoConnections { pos } => write!(f, "Start point {:?} has no connected neighbours", pos),
+            TraceError::OutOfBounds { pos, dir } =>
+                write!(f, "Loop leaves the grid from {:?} going {:?}", pos, dir),
+            TraceError::Disconnected { pos, dir } =>
+                write!(f, "Loop is broken at {:?} (cannot enter going {:?})", pos, dir),
+        }
+    }
+}
+
+impl Error for TraceError {}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use Dir::{Down, Left, Right, Up};
+
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    fn pipes(c: char) -> Option<(Dir, Dir)> {
+        match c {
+            '|' => Some((Up, Down)),
+            '-' => Some((Left, Right)),
+            'L' => Some((Up, Right)),
+            'J' => Some((Up, Left)),
+            '7' => Some((Down, Left)),
+            'F' => Some((Down, Right)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_trace_loop() {
+        let grid = AGrid::from_lines(indoc! {"
+            7-F7-
+            .FJ|7
+            SJLL7
+            |F--J
+            LJ.LJ"
+        });
+        let trace = trace_loop(&grid, &Point::new(0, 2), pipes).unwrap();
+        assert_eq!(trace.start, (Right, Down));
+        assert_eq!(trace.poly.get_perimeter(), 16);
+        assert_eq!(trace.poly.get_internal_area(), 1);
+    }
+
+    #[test]
+    fn test_trace_errors() {
+        let grid = AGrid::from_lines(indoc! {"
+            .....
+            .S-7.
+            .|.|.
+            .L-7.
+            ....."
+        });
+        let start = Point::new(1, 1);
+        assert_eq!(trace_loop(&grid, &start, pipes).err(), Some(TraceError::Disconnected { pos: Point::new(3, 3), dir: Down }));
+
+        let grid = AGrid::from_lines(indoc! {"
+            .S-7
+            .|.|
+            .L-|"
+        });
+        let start = Point::new(1, 0);
+        assert_eq!(trace_loop(&grid, &start, pipes).err(), Some(TraceError::OutOfBounds { pos: Point::new(3, 2), dir: Down }));
+
+        let grid = AGrid::from_lines("7S.\n.-.");
+        let start = Point::new(1, 0);
+        assert_eq!(trace_loop(&grid, &start, pipes).err(), Some(TraceError::NoConnections { pos: start }));
+    }
+}
@@ -1,25 +1,123 @@
+use std::hash::{Hash, Hasher};
+use std::iter::FusedIterator;
+
 use num_traits::PrimInt;
+use rustc_hash::FxHasher;
 use strum_macros::{Display, EnumIter};
 
-#[derive(Debug)]
+/// Returns a 64-bit fingerprint of a hashable value.
+///
+/// Unlike the standard library's `DefaultHasher`, the result is stable across runs (no random
+/// seed), so fingerprints can be logged and compared between executions. Fingerprints can collide,
+/// so they should only be used as a fast pre-check before exact equality (see `Fingerprinted`).
+pub fn fingerprint<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = FxHasher::default();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Returns a 64-bit fingerprint of a collection of hashable values which is independent of the
+/// order in which they are given. This is useful for states held in hash sets or maps.
+#[allow(unused)]
+pub fn fingerprint_unordered<T: Hash, It: IntoIterator<Item=T>>(values: It) -> u64 {
+    values.into_iter().map(|v| mix(fingerprint(&v))).fold(0u64, |acc, h| acc.wrapping_add(h))
+}
+
+// The SplitMix64 finalizer. FxHasher is close to linear for small integers, so element hashes must
+// be scrambled before summing or, for example, {1, 4} and {2, 3} would collide.
+fn mix(mut h: u64) -> u64 {
+    h = (h ^ (h >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    h ^ (h >> 31)
+}
+
+/// A value paired with its precomputed fingerprint.
+///
+/// Hashing uses only the fingerprint, which makes `Fingerprinted` values cheap to use as keys for
+/// large states (e.g. whole grids), while equality still compares the full value so there is no
+/// risk of false matches.
+#[derive(Clone, Debug)]
+pub struct Fingerprinted<T> {
+    fingerprint: u64,
+    value: T,
+}
+
+#[allow(unused)]
+impl<T: Hash> Fingerprinted<T> {
+    pub fn new(value: T) -> Fingerprinted<T> {
+        Fingerprinted { fingerprint: fingerprint(&value), value }
+    }
+
+    pub fn fingerprint(&self) -> u64 { self.fingerprint }
+
+    pub fn value(&self) -> &T { &self.value }
+
+    pub fn into_value(self) -> T { self.value }
+}
+
+impl<T: Eq> PartialEq for Fingerprinted<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.fingerprint == other.fingerprint && self.value == other.value
+    }
+}
+
+impl<T: Eq> Eq for Fingerprinted<T> {}
+
+impl<T> Hash for Fingerprinted<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.fingerprint);
+    }
+}
+
+/// An iterator over primitive integers from `start` to `end` (inclusive), in either direction.
+///
+/// Values are computed from their offset to `start`, so no value beyond `end` is ever calculated
+/// (e.g. iterating up to the type's maximum value cannot overflow).
+#[derive(Clone, Debug)]
 pub struct PrimIter<I: PrimInt> {
     start: I,
-    end: I,
     step: I,
-    cur: Option<I>,
+    ascending: bool,
+    len: usize,
+    // Indices of the next values to be returned from the front and back (exclusive).
+    front: usize,
+    back: usize,
 }
 
 impl<I: PrimInt> PrimIter<I> {
+    /// Creates an iterator from `start`, stepping by `step` towards `end` and stopping at the last
+    /// value which does not pass `end`. The step must be positive, and the number of values must
+    /// fit in a `usize`.
     pub fn new(start: I, end: I, step: I) -> PrimIter<I> {
-        PrimIter { start, end, step, cur: Some(start) }
+        assert!(step > I::zero(), "Step must be positive");
+        let ascending = end >= start;
+        let len = (wide_abs_diff(start, end) / step.to_u128().unwrap())
+            .try_into().ok().and_then(|n: usize| n.checked_add(1)).expect("Too many values");
+        PrimIter { start, step, ascending, len, front: 0, back: len }
     }
 
-    pub fn get(&self) -> Option<I> { self.cur }
+    /// Returns the `i`th value of the original range, regardless of how many values have been
+    /// consumed. Panics if `i` is beyond the end of the range.
+    pub fn value_at(&self, i: usize) -> I {
+        // The offset can only fail to fit in the type for signed ranges which span zero.
+        assert!(i < self.len, "Index out of range");
+        let value = I::from(i).and_then(|i| self.step.checked_mul(&i)).and_then(|offset| {
+            if self.ascending { self.start.checked_add(&offset) } else { self.start.checked_sub(&offset) }
+        });
+        value.unwrap_or_else(|| {
+            let offset = self.step.to_i128().unwrap() * i as i128;
+            let value = self.start.to_i128().unwrap() + if self.ascending { offset } else { -offset };
+            I::from(value).unwrap()
+        })
+    }
+}
 
-    pub fn reset_and_get(&mut self) -> I {
-        self.cur = Some(self.start);
-        self.next();
-        self.start
+// The absolute difference between any two primitive integers, which cannot overflow.
+fn wide_abs_diff<I: PrimInt>(a: I, b: I) -> u128 {
+    match (a.to_i128(), b.to_i128()) {
+        (Some(a), Some(b)) => a.abs_diff(b),
+        // Only possible for large u128 values.
+        _ => a.to_u128().unwrap().abs_diff(b.to_u128().unwrap()),
     }
 }
 
@@ -27,22 +125,38 @@ impl<I: PrimInt> Iterator for PrimIter<I> {
     type Item = I;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let value = self.cur;
-        if let Some(n) = value {
-            if self.end >= self.start {
-                self.cur = if self.end - n >= self.step {
-                    Some(n + self.step)
-                } else { None };
-            } else {
-                self.cur = if n - self.end >= self.step {
-                    Some(n - self.step)
-                } else { None }
-            }
+        if self.front == self.back {
+            return None;
         }
-        return value;
+        self.front += 1;
+        Some(self.value_at(self.front - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.front = self.front.saturating_add(n).min(self.back);
+        self.next()
     }
 }
 
+impl<I: PrimInt> DoubleEndedIterator for PrimIter<I> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(self.value_at(self.back))
+    }
+}
+
+impl<I: PrimInt> ExactSizeIterator for PrimIter<I> {}
+
+impl<I: PrimInt> FusedIterator for PrimIter<I> {}
+
 #[cfg(test)]
 mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
@@ -53,6 +167,49 @@ mod tests {
         let it: PrimIter<usize> = PrimIter::new(20, 10, 2);
         assert_eq!(it.collect::<Vec<_>>(), [20, 18, 16, 14, 12, 10]);
     }
+
+    #[test]
+    fn test_iter_double_ended() {
+        let mut it: PrimIter<u8> = PrimIter::new(1, 10, 3);
+        assert_eq!(it.len(), 4);
+        assert_eq!(it.next_back(), Some(10));
+        assert_eq!(it.next(), Some(1));
+        assert_eq!(it.len(), 2);
+        assert_eq!(it.rev().collect::<Vec<_>>(), [7, 4]);
+
+        let mut it: PrimIter<u8> = PrimIter::new(3, 0, 1);
+        assert_eq!(it.nth(2), Some(1));
+        assert_eq!(it.nth(5), None);
+        // Fused, so it stays finished.
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_iter_at_limits() {
+        let it: PrimIter<u8> = PrimIter::new(250, 255, 1);
+        assert_eq!(it.collect::<Vec<_>>(), [250, 251, 252, 253, 254, 255]);
+        let it: PrimIter<i8> = PrimIter::new(-126, -128, 1);
+        assert_eq!(it.collect::<Vec<_>>(), [-126, -127, -128]);
+        // The step would overflow past the end, but that value is never calculated.
+        let it: PrimIter<u8> = PrimIter::new(0, 255, 200);
+        assert_eq!(it.collect::<Vec<_>>(), [0, 200]);
+        let it: PrimIter<i8> = PrimIter::new(-128, 127, 85);
+        assert_eq!(it.collect::<Vec<_>>(), [-128, -43, 42, 127]);
+        assert_eq!(PrimIter::new(127i8, -128, 1).len(), 256);
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        assert_eq!(fingerprint(&vec![1, 2, 3]), fingerprint(&vec![1, 2, 3]));
+        assert_ne!(fingerprint(&vec![1, 2, 3]), fingerprint(&vec![3, 2, 1]));
+        assert_eq!(fingerprint_unordered([1, 2, 3]), fingerprint_unordered([3, 1, 2]));
+        assert_ne!(fingerprint_unordered([1, 4]), fingerprint_unordered([2, 3]));
+
+        let a = Fingerprinted::new("abc".to_string());
+        assert_eq!(a.fingerprint(), fingerprint(&"abc".to_string()));
+        assert_eq!(a, Fingerprinted::new("abc".to_string()));
+        assert_ne!(a, Fingerprinted::new("abd".to_string()));
+    }
 }
 
 #[derive(Display, EnumIter, Debug)]
@@ -1,4 +1,11 @@
-use num_traits::PrimInt;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::iter::FusedIterator;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+use num_traits::{PrimInt, Signed, WrappingAdd, WrappingSub};
 
 use xy::Dir::{Down, Left, Right, Up};
 
@@ -14,16 +21,279 @@ pub enum Dir {
     Down,
     /// Decreasing X.
     Left,
-    /// Decreasing X.
+    /// Decreasing Y.
     Up,
 }
 
+#[allow(unused)]
+impl Dir {
+    /// Returns all directions, in clockwise order starting from `Right`.
+    pub fn all() -> [Dir; 4] {
+        [Right, Down, Left, Up]
+    }
+
+    /// Returns the direction for the given orientation, in which coordinates increase (positive)
+    /// or decrease (negative).
+    pub fn from_orientation(orientation: Orientation, positive: bool) -> Dir {
+        match (orientation, positive) {
+            (Orientation::Horizontal, true) => Right,
+            (Orientation::Horizontal, false) => Left,
+            (Orientation::Vertical, true) => Down,
+            (Orientation::Vertical, false) => Up,
+        }
+    }
+
+    /// Returns the direction 90 degrees anti-clockwise from this one (as seen with (0, 0) at the
+    /// top left).
+    pub fn turn_left(&self) -> Dir {
+        match self {
+            Right => Up,
+            Down => Right,
+            Left => Down,
+            Up => Left,
+        }
+    }
+
+    /// Returns the direction 90 degrees clockwise from this one (as seen with (0, 0) at the top
+    /// left).
+    pub fn turn_right(&self) -> Dir {
+        match self {
+            Right => Down,
+            Down => Left,
+            Left => Up,
+            Up => Right,
+        }
+    }
+
+    /// Returns the opposite direction.
+    pub fn reverse(&self) -> Dir {
+        match self {
+            Right => Left,
+            Down => Up,
+            Left => Right,
+            Up => Down,
+        }
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        match self {
+            Right | Left => Orientation::Horizontal,
+            Down | Up => Orientation::Vertical,
+        }
+    }
+
+    /// Returns the unit vector for moving one step in this direction.
+    pub fn to_vector<I: PrimInt + Signed>(self) -> Vector<I> {
+        match self {
+            Right => Vector::new(I::one(), I::zero()),
+            Down => Vector::new(I::zero(), I::one()),
+            Left => Vector::new(-I::one(), I::zero()),
+            Up => Vector::new(I::zero(), -I::one()),
+        }
+    }
+}
+
+/// The textual encodings of orthogonal directions commonly found in puzzle input.
+///
+/// The characters used by each encoding are all distinct, so `Dir::from_str()` and
+/// `Dir::try_from(char)` accept any of them, while `DirEncoding::parse()` accepts only one.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DirEncoding {
+    /// `R`, `D`, `L`, `U`.
+    Letters,
+    /// `>`, `v`, `<`, `^`.
+    Arrows,
+    /// `E`, `S`, `W`, `N`.
+    Compass,
+    /// `0`, `1`, `2`, `3` (clockwise from `Right`).
+    Digits,
+}
+
+#[allow(unused)]
+impl DirEncoding {
+    pub fn all() -> [DirEncoding; 4] {
+        [DirEncoding::Letters, DirEncoding::Arrows, DirEncoding::Compass, DirEncoding::Digits]
+    }
+
+    // Characters for each direction, in the order of Dir::all().
+    fn chars(&self) -> [char; 4] {
+        match self {
+            DirEncoding::Letters => ['R', 'D', 'L', 'U'],
+            DirEncoding::Arrows => ['>', 'v', '<', '^'],
+            DirEncoding::Compass => ['E', 'S', 'W', 'N'],
+            DirEncoding::Digits => ['0', '1', '2', '3'],
+        }
+    }
+
+    /// Parses a single character in this encoding.
+    pub fn parse(&self, c: char) -> Result<Dir, ParseDirError> {
+        self.chars().iter().position(|&e| e == c)
+            .map(|i| Dir::all()[i])
+            .ok_or_else(|| ParseDirError { input: c.to_string() })
+    }
+
+    /// Returns the character representing the given direction in this encoding.
+    pub fn encode(&self, dir: Dir) -> char {
+        self.chars()[dir as usize]
+    }
+}
+
+/// The error returned when text is not a recognized direction.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseDirError {
+    input: String,
+}
+
+impl Display for ParseDirError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown direction: {:?}", self.input)
+    }
+}
+
+impl Error for ParseDirError {}
+
+impl TryFrom<char> for Dir {
+    type Error = ParseDirError;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        DirEncoding::all().iter()
+            .find_map(|e| e.parse(c).ok())
+            .ok_or_else(|| ParseDirError { input: c.to_string() })
+    }
+}
+
+impl FromStr for Dir {
+    type Err = ParseDirError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Dir::try_from(c),
+            _ => Err(ParseDirError { input: s.to_string() }),
+        }
+    }
+}
+
+/// Formats a direction using `DirEncoding::Letters`.
+impl Display for Dir {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", DirEncoding::Letters.encode(*self))
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum Orientation {
     Horizontal,
     Vertical,
 }
 
+#[allow(unused)]
+impl Orientation {
+    /// Returns the orientation at right-angles to this one.
+    pub fn perpendicular(&self) -> Orientation {
+        match self {
+            Orientation::Horizontal => Orientation::Vertical,
+            Orientation::Vertical => Orientation::Horizontal,
+        }
+    }
+}
+
+/// An eight-way compass direction in the X/Y plane, where (0, 0) is considered "top left" (so
+/// "North" is decreasing Y).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Dir8 {
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+    North,
+    NorthEast,
+}
+
+#[allow(unused)]
+impl Dir8 {
+    /// Returns all directions, in clockwise order starting from `East` (matching `Dir::all()`).
+    pub fn all() -> [Dir8; 8] {
+        use Dir8::*;
+        [East, SouthEast, South, SouthWest, West, NorthWest, North, NorthEast]
+    }
+
+    /// Returns the diagonal directions, in clockwise order starting from `SouthEast`.
+    pub fn diagonals() -> [Dir8; 4] {
+        use Dir8::*;
+        [SouthEast, SouthWest, NorthWest, NorthEast]
+    }
+
+    pub fn is_diagonal(&self) -> bool {
+        self.index() % 2 == 1
+    }
+
+    /// Returns the direction rotated clockwise by the given number of 45 degree steps (negative
+    /// values rotate anti-clockwise).
+    pub fn rotate(&self, steps: i32) -> Dir8 {
+        Dir8::all()[(self.index() as i32 + steps).rem_euclid(8) as usize]
+    }
+
+    /// Returns the opposite direction.
+    pub fn reverse(&self) -> Dir8 {
+        self.rotate(4)
+    }
+
+    /// Returns the horizontal and vertical components of this direction. Exactly one of these is
+    /// `None` for orthogonal directions.
+    pub fn components(&self) -> (Option<Dir>, Option<Dir>) {
+        use Dir8::*;
+        match self {
+            East => (Some(Right), None),
+            SouthEast => (Some(Right), Some(Down)),
+            South => (None, Some(Down)),
+            SouthWest => (Some(Left), Some(Down)),
+            West => (Some(Left), None),
+            NorthWest => (Some(Left), Some(Up)),
+            North => (None, Some(Up)),
+            NorthEast => (Some(Right), Some(Up)),
+        }
+    }
+
+    /// Returns the vector for moving one step in this direction (diagonal steps move by one unit
+    /// on both axes).
+    pub fn to_vector<I: PrimInt + Signed>(self) -> Vector<I> {
+        let (h, v) = self.components();
+        let zero = Vector::zero();
+        h.map(|d| d.to_vector()).unwrap_or(zero) + v.map(|d| d.to_vector()).unwrap_or(zero)
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl From<Dir> for Dir8 {
+    fn from(d: Dir) -> Self {
+        match d {
+            Right => Dir8::East,
+            Down => Dir8::South,
+            Left => Dir8::West,
+            Up => Dir8::North,
+        }
+    }
+}
+
+impl TryFrom<Dir8> for Dir {
+    /// Diagonal directions cannot be converted, and are returned as the error.
+    type Error = Dir8;
+
+    fn try_from(d: Dir8) -> Result<Self, Self::Error> {
+        match d.components() {
+            (Some(h), None) => Ok(h),
+            (None, Some(v)) => Ok(v),
+            _ => Err(d),
+        }
+    }
+}
+
 /// An absolute point in the X/Y plane.
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct Point<I: PrimInt> {
@@ -43,8 +313,11 @@ impl<I: PrimInt> Point<I> {
         Point { x: self.x + x, y: self.y + y }
     }
 
+    /// Returns the point moved by `len` in the given direction. Moving by zero is a no-op.
+    ///
+    /// This panics on overflow in debug builds (and wraps in release builds), so for unsigned
+    /// types near zero, or values near the type's limits, use one of the explicit variants below.
     pub fn move_by(&self, len: I, dir: Dir) -> Point<I> {
-        assert!(!len.is_zero(), "Must move by non-zero length");
         let mut x = self.x;
         let mut y = self.y;
         match dir {
@@ -55,6 +328,78 @@ impl<I: PrimInt> Point<I> {
         };
         Point { x, y }
     }
+
+    /// Returns the point moved by `len` in the given direction, or `None` if the result cannot be
+    /// represented (e.g. moving `Up` from `y = 0` for unsigned types).
+    pub fn checked_move_by(&self, len: I, dir: Dir) -> Option<Point<I>> {
+        Some(match dir {
+            Right => Point { x: self.x.checked_add(&len)?, y: self.y },
+            Down => Point { x: self.x, y: self.y.checked_add(&len)? },
+            Left => Point { x: self.x.checked_sub(&len)?, y: self.y },
+            Up => Point { x: self.x, y: self.y.checked_sub(&len)? },
+        })
+    }
+
+    /// Returns the point moved by `len` in the given direction, clamped to the limits of the type.
+    pub fn saturating_move_by(&self, len: I, dir: Dir) -> Point<I> {
+        match dir {
+            Right => Point { x: self.x.saturating_add(len), y: self.y },
+            Down => Point { x: self.x, y: self.y.saturating_add(len) },
+            Left => Point { x: self.x.saturating_sub(len), y: self.y },
+            Up => Point { x: self.x, y: self.y.saturating_sub(len) },
+        }
+    }
+}
+
+#[allow(unused)]
+impl<I: PrimInt + WrappingAdd + WrappingSub> Point<I> {
+    /// Returns the point moved by `len` in the given direction, wrapping around at the limits of
+    /// the type (e.g. for toroidal spaces with a power-of-two size).
+    pub fn wrapping_move_by(&self, len: I, dir: Dir) -> Point<I> {
+        match dir {
+            Right => Point { x: self.x.wrapping_add(&len), y: self.y },
+            Down => Point { x: self.x, y: self.y.wrapping_add(&len) },
+            Left => Point { x: self.x.wrapping_sub(&len), y: self.y },
+            Up => Point { x: self.x, y: self.y.wrapping_sub(&len) },
+        }
+    }
+}
+
+#[allow(unused)]
+impl<I: PrimInt> Point<I> {
+    /// Returns the Manhattan (taxicab) distance to another point.
+    pub fn manhattan(&self, other: &Point<I>) -> I {
+        abs_diff(self.x, other.x) + abs_diff(self.y, other.y)
+    }
+
+    /// Returns the Chebyshev (chessboard) distance to another point.
+    pub fn chebyshev(&self, other: &Point<I>) -> I {
+        abs_diff(self.x, other.x).max(abs_diff(self.y, other.y))
+    }
+
+    /// Returns the square of the Euclidean distance to another point.
+    pub fn euclidean_sq(&self, other: &Point<I>) -> I {
+        let dx = abs_diff(self.x, other.x);
+        let dy = abs_diff(self.y, other.y);
+        dx * dx + dy * dy
+    }
+
+    /// Converts to a point with a different coordinate type, returning `None` if either
+    /// coordinate cannot be represented (e.g. a negative value for an unsigned type).
+    pub fn try_cast<J: PrimInt>(&self) -> Option<Point<J>> {
+        Some(Point { x: J::from(self.x)?, y: J::from(self.y)? })
+    }
+
+    /// Converts to a point with a different coordinate type, panicking if either coordinate
+    /// cannot be represented.
+    pub fn cast<J: PrimInt>(&self) -> Point<J> {
+        self.try_cast().expect("Point coordinates out of range for target type")
+    }
+}
+
+// Absolute difference which cannot underflow for unsigned types.
+pub(crate) fn abs_diff<I: PrimInt>(a: I, b: I) -> I {
+    if a > b { a - b } else { b - a }
 }
 
 impl<I: PrimInt> From<(I, I)> for Point<I> {
@@ -63,18 +408,337 @@ impl<I: PrimInt> From<(I, I)> for Point<I> {
     }
 }
 
+/// A relative offset in the X/Y plane.
+///
+/// Points and vectors are distinct types so that only meaningful arithmetic is possible (e.g. you
+/// can add a vector to a point, or subtract two points to get a vector, but not add two points).
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Vector<I: PrimInt> {
+    pub x: I,
+    pub y: I,
+}
+
+#[allow(unused)]
+impl<I: PrimInt> Vector<I> {
+    pub fn new(x: I, y: I) -> Self { Vector { x, y } }
+
+    pub fn zero() -> Self { Vector { x: I::zero(), y: I::zero() } }
+
+    /// Returns the Manhattan length of this vector.
+    pub fn manhattan(&self) -> I {
+        Point::origin().manhattan(&Point { x: self.x, y: self.y })
+    }
+
+    /// Converts to a vector with a different coordinate type, returning `None` if either
+    /// component cannot be represented.
+    pub fn try_cast<J: PrimInt>(&self) -> Option<Vector<J>> {
+        Some(Vector { x: J::from(self.x)?, y: J::from(self.y)? })
+    }
+}
+
+impl<I: PrimInt> Add<Vector<I>> for Point<I> {
+    type Output = Point<I>;
+
+    fn add(self, v: Vector<I>) -> Point<I> {
+        Point { x: self.x + v.x, y: self.y + v.y }
+    }
+}
+
+impl<I: PrimInt> AddAssign<Vector<I>> for Point<I> {
+    fn add_assign(&mut self, v: Vector<I>) {
+        *self = *self + v;
+    }
+}
+
+impl<I: PrimInt> Sub<Vector<I>> for Point<I> {
+    type Output = Point<I>;
+
+    fn sub(self, v: Vector<I>) -> Point<I> {
+        Point { x: self.x - v.x, y: self.y - v.y }
+    }
+}
+
+impl<I: PrimInt> SubAssign<Vector<I>> for Point<I> {
+    fn sub_assign(&mut self, v: Vector<I>) {
+        *self = *self - v;
+    }
+}
+
+impl<I: PrimInt> Sub for Point<I> {
+    type Output = Vector<I>;
+
+    fn sub(self, p: Point<I>) -> Vector<I> {
+        Vector { x: self.x - p.x, y: self.y - p.y }
+    }
+}
+
+impl<I: PrimInt> Add for Vector<I> {
+    type Output = Vector<I>;
+
+    fn add(self, v: Vector<I>) -> Vector<I> {
+        Vector { x: self.x + v.x, y: self.y + v.y }
+    }
+}
+
+impl<I: PrimInt> Sub for Vector<I> {
+    type Output = Vector<I>;
+
+    fn sub(self, v: Vector<I>) -> Vector<I> {
+        Vector { x: self.x - v.x, y: self.y - v.y }
+    }
+}
+
+impl<I: PrimInt + Signed> Neg for Vector<I> {
+    type Output = Vector<I>;
+
+    fn neg(self) -> Vector<I> {
+        Vector { x: -self.x, y: -self.y }
+    }
+}
+
+impl<I: PrimInt> Mul<I> for Vector<I> {
+    type Output = Vector<I>;
+
+    fn mul(self, n: I) -> Vector<I> {
+        Vector { x: self.x * n, y: self.y * n }
+    }
+}
+
+impl<I: PrimInt> From<(I, I)> for Vector<I> {
+    fn from(xy: (I, I)) -> Self {
+        Vector { x: xy.0, y: xy.1 }
+    }
+}
+
+/// An axis-aligned rectangle in the X/Y plane, covering all points `p` where `min <= p <= max` on
+/// both axes (i.e. both corners are inclusive, so a rectangle is never empty).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Rect<I: PrimInt> {
+    min: Point<I>,
+    max: Point<I>,
+}
+
+#[allow(unused)]
+impl<I: PrimInt> Rect<I> {
+    /// Creates a rectangle from any two opposite corners.
+    pub fn from_corners(a: Point<I>, b: Point<I>) -> Rect<I> {
+        Rect {
+            min: Point::new(a.x.min(b.x), a.y.min(b.y)),
+            max: Point::new(a.x.max(b.x), a.y.max(b.y)),
+        }
+    }
+
+    /// Returns the smallest rectangle containing all the given points, or `None` if there are no
+    /// points.
+    pub fn bounding<'a, It: IntoIterator<Item=&'a Point<I>>>(points: It) -> Option<Rect<I>> where I: 'a {
+        points.into_iter().fold(None, |r: Option<Rect<I>>, p| Some(match r {
+            Some(r) => r.union(&Rect { min: *p, max: *p }),
+            None => Rect { min: *p, max: *p },
+        }))
+    }
+
+    /// The corner with minimum X and Y.
+    pub fn top_left(&self) -> Point<I> { self.min }
+
+    pub fn top_right(&self) -> Point<I> { Point::new(self.max.x, self.min.y) }
+
+    pub fn bottom_left(&self) -> Point<I> { Point::new(self.min.x, self.max.y) }
+
+    /// The corner with maximum X and Y.
+    pub fn bottom_right(&self) -> Point<I> { self.max }
+
+    /// Returns the number of distinct X coordinates covered (which is at least 1).
+    pub fn width(&self) -> I { self.max.x - self.min.x + I::one() }
+
+    /// Returns the number of distinct Y coordinates covered (which is at least 1).
+    pub fn height(&self) -> I { self.max.y - self.min.y + I::one() }
+
+    /// Returns the number of points in the rectangle.
+    pub fn area(&self) -> I { self.width() * self.height() }
+
+    pub fn contains(&self, p: &Point<I>) -> bool {
+        self.min.x <= p.x && p.x <= self.max.x && self.min.y <= p.y && p.y <= self.max.y
+    }
+
+    /// Returns the rectangle of points common to both rectangles, if any.
+    pub fn intersection(&self, other: &Rect<I>) -> Option<Rect<I>> {
+        let min = Point::new(self.min.x.max(other.min.x), self.min.y.max(other.min.y));
+        let max = Point::new(self.max.x.min(other.max.x), self.max.y.min(other.max.y));
+        if min.x <= max.x && min.y <= max.y { Some(Rect { min, max }) } else { None }
+    }
+
+    /// Returns the smallest rectangle containing both rectangles.
+    pub fn union(&self, other: &Rect<I>) -> Rect<I> {
+        Rect {
+            min: Point::new(self.min.x.min(other.min.x), self.min.y.min(other.min.y)),
+            max: Point::new(self.max.x.max(other.max.x), self.max.y.max(other.max.y)),
+        }
+    }
+
+    /// Returns all points in the rectangle in raster order (by row, from the top-left).
+    pub fn points(&self) -> Piter<I> {
+        Piter::new(self.min, Right, self.width(), Down, self.height())
+    }
+
+    /// Returns all points in the rectangle in the given order, starting from the top-left.
+    pub fn traverse(&self, order: Traversal) -> Titer<I> {
+        let count = self.width().to_usize().unwrap() * self.height().to_usize().unwrap();
+        Titer { order, bounds: *self, ring: *self, dir: Right, next: self.min, remaining: count }
+    }
+}
+
+/// The orders in which the points of a rectangle can be visited (see `Rect::traverse()`).
+#[allow(unused)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Traversal {
+    /// By row, left to right (the same as `Piter`).
+    Raster,
+    /// By column, top to bottom.
+    ColumnMajor,
+    /// By row, alternately left to right and right to left (i.e. boustrophedon order).
+    Snake,
+    /// Clockwise around the edge, then spiralling inwards.
+    Spiral,
+    /// By anti-diagonal (where `x + y` is constant), each visited from top-right to bottom-left.
+    Diagonal,
+}
+
+/// An iterator over the points of a rectangle in a given `Traversal` order.
 #[derive(Debug)]
+pub struct Titer<I: PrimInt> {
+    order: Traversal,
+    bounds: Rect<I>,
+    // The remaining outer ring and current direction for spiral traversal.
+    ring: Rect<I>,
+    dir: Dir,
+    next: Point<I>,
+    remaining: usize,
+}
+
+impl<I: PrimInt> Titer<I> {
+    // Only called when there is another point to visit, so will never step outside the bounds
+    // (which is important to avoid overflow for unsigned types).
+    fn step(&mut self, p: Point<I>) -> Point<I> {
+        let one = I::one();
+        let (min, max) = (self.bounds.min, self.bounds.max);
+        match self.order {
+            Traversal::Raster =>
+                if p.x < max.x { Point::new(p.x + one, p.y) } else { Point::new(min.x, p.y + one) },
+            Traversal::ColumnMajor =>
+                if p.y < max.y { Point::new(p.x, p.y + one) } else { Point::new(p.x + one, min.y) },
+            Traversal::Snake => {
+                let rightwards = ((p.y - min.y) & one).is_zero();
+                if rightwards && p.x < max.x {
+                    Point::new(p.x + one, p.y)
+                } else if !rightwards && p.x > min.x {
+                    Point::new(p.x - one, p.y)
+                } else {
+                    Point::new(p.x, p.y + one)
+                }
+            }
+            Traversal::Diagonal => {
+                if p.x > min.x && p.y < max.y {
+                    Point::new(p.x - one, p.y + one)
+                } else {
+                    // Start the next anti-diagonal from the top or right edge.
+                    let k = (p.x - min.x) + (p.y - min.y) + one;
+                    let w = self.bounds.width();
+                    if k < w { Point::new(min.x + k, min.y) } else { Point::new(max.x, min.y + (k - (w - one))) }
+                }
+            }
+            Traversal::Spiral => {
+                // When we reach the end of a side, that side of the ring is complete, so shrink it.
+                let ring = &mut self.ring;
+                let at_end = match self.dir {
+                    Right => p.x == ring.max.x,
+                    Down => p.y == ring.max.y,
+                    Left => p.x == ring.min.x,
+                    Up => p.y == ring.min.y,
+                };
+                if at_end {
+                    match self.dir {
+                        Right => ring.min.y = ring.min.y + one,
+                        Down => ring.max.x = ring.max.x - one,
+                        Left => ring.max.y = ring.max.y - one,
+                        Up => ring.min.x = ring.min.x + one,
+                    }
+                    self.dir = self.dir.turn_right();
+                }
+                p.move_by(one, self.dir)
+            }
+        }
+    }
+}
+
+impl<I: PrimInt> Iterator for Titer<I> {
+    type Item = Point<I>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let p = self.next;
+        self.remaining -= 1;
+        if self.remaining > 0 {
+            self.next = self.step(p);
+        }
+        Some(p)
+    }
+}
+
+/// An iterator over a fixed number of points in a straight line from a start point, in any of
+/// the eight compass directions.
+#[derive(Debug)]
+pub struct Ray<I: PrimInt> {
+    next: Point<I>,
+    dir: Dir8,
+    remaining: I,
+}
+
+impl<I: PrimInt> Ray<I> {
+    /// Creates a ray of `len` points (including the start point), which may be zero.
+    pub fn new(start: Point<I>, dir: Dir8, len: I) -> Ray<I> {
+        Ray { next: start, dir, remaining: len }
+    }
+}
+
+impl<I: PrimInt> Iterator for Ray<I> {
+    type Item = Point<I>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining.is_zero() {
+            return None;
+        }
+        let p = self.next;
+        self.remaining = self.remaining - I::one();
+        // Only step if there's another point, since the step after the last point may not be
+        // representable (e.g. moving left from x=0 for unsigned types).
+        if !self.remaining.is_zero() {
+            let (h, v) = self.dir.components();
+            self.next = h.map(|d| self.next.move_by(I::one(), d)).unwrap_or(self.next);
+            self.next = v.map(|d| self.next.move_by(I::one(), d)).unwrap_or(self.next);
+        }
+        Some(p)
+    }
+}
+
+/// An iterator over the points of a rectangular region, visiting all points along the "minor"
+/// axis (which must be horizontal) for each point of the "major" axis in turn.
+#[derive(Clone, Debug)]
 pub struct Piter<I: PrimInt> {
     min_axis: PrimIter<I>,
     maj_axis: PrimIter<I>,
+    // Indices of the next points to be returned from the front and back (exclusive).
+    front: usize,
+    back: usize,
 }
 
 impl<I: PrimInt> Piter<I> {
     pub fn new(start: Point<I>, min_dir: Dir, min_len: I, maj_dir: Dir, maj_len: I) -> Piter<I> {
-        Piter {
-            min_axis: Piter::get_iter(start, min_dir, min_len),
-            maj_axis: Piter::get_iter(start, maj_dir, maj_len),
-        }
+        let min_axis = Piter::get_iter(start, min_dir, min_len);
+        let maj_axis = Piter::get_iter(start, maj_dir, maj_len);
+        let back = min_axis.len().checked_mul(maj_axis.len()).expect("Too many points");
+        Piter { min_axis, maj_axis, front: 0, back }
     }
 
     fn get_iter(start: Point<I>, dir: Dir, len: I) -> PrimIter<I> {
@@ -87,28 +751,53 @@ impl<I: PrimInt> Piter<I> {
             Up => PrimIter::new(start.y, start.y - offset, I::one()),
         }
     }
+
+    fn point_at(&self, i: usize) -> Point<I> {
+        // The axis iterators are never advanced, so their length is that of the full axis.
+        let min_len = self.min_axis.len();
+        Point { x: self.min_axis.value_at(i % min_len), y: self.maj_axis.value_at(i / min_len) }
+    }
 }
 
 impl<I: PrimInt> Iterator for Piter<I> {
     type Item = Point<I>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(y) = self.maj_axis.get() {
-            if let Some(x) = self.min_axis.next() {
-                return Some(Point { x, y });
-            }
-            self.maj_axis.next();
-            if let Some(y) = self.maj_axis.get() {
-                return Some(Point { x: self.min_axis.reset_and_get(), y });
-            }
+        if self.front == self.back {
+            return None;
         }
-        return None;
+        self.front += 1;
+        Some(self.point_at(self.front - 1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.front = self.front.saturating_add(n).min(self.back);
+        self.next()
     }
 }
 
+impl<I: PrimInt> DoubleEndedIterator for Piter<I> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(self.point_at(self.back))
+    }
+}
+
+impl<I: PrimInt> ExactSizeIterator for Piter<I> {}
+
+impl<I: PrimInt> FusedIterator for Piter<I> {}
+
 #[cfg(test)]
 mod tests {
-    use Dir::{Left, Right, Up};
+    use Dir::{Down, Left, Right, Up};
 
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
@@ -131,4 +820,187 @@ mod tests {
             Point::new(1, 0),
             Point::new(0, 0)]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_vector_ops() {
+        let p: Point<i32> = Point::new(3, -2);
+        let v: Vector<i32> = Vector::new(1, 4);
+        assert_eq!(p + v, Point::new(4, 2));
+        assert_eq!(p - v, Point::new(2, -6));
+        assert_eq!((p + v) - p, v);
+        assert_eq!(-v * 3, Vector::new(-3, -12));
+        assert_eq!(v.manhattan(), 5);
+
+        let mut q = p;
+        q += v;
+        q -= v * 2;
+        assert_eq!(q, Point::new(2, -6));
+    }
+
+    #[test]
+    fn test_distances() {
+        // Unsigned coordinates must not underflow when the "other" point is larger.
+        let a: Point<usize> = Point::new(1, 5);
+        let b: Point<usize> = Point::new(4, 1);
+        assert_eq!(a.manhattan(&b), 7);
+        assert_eq!(b.manhattan(&a), 7);
+        assert_eq!(a.chebyshev(&b), 4);
+        assert_eq!(a.euclidean_sq(&b), 25);
+    }
+
+    #[test]
+    fn test_cast() {
+        let p: Point<i32> = Point::new(3, -2);
+        assert_eq!(p.try_cast::<usize>(), None);
+        assert_eq!(p.cast::<i64>(), Point::new(3i64, -2i64));
+        assert_eq!(Point::new(7usize, 9usize).cast::<i32>(), Point::new(7, 9));
+    }
+
+    #[test]
+    fn test_turns() {
+        for d in Dir::all() {
+            assert_eq!(d.turn_left().turn_right(), d);
+            assert_eq!(d.turn_right().turn_right(), d.reverse());
+            assert_ne!(d.turn_left().orientation(), d.orientation());
+            assert_eq!(Dir::from_orientation(d.orientation(), d == Right || d == Down), d);
+            assert_eq!(d.to_vector::<i32>(), -d.reverse().to_vector());
+        }
+        assert_eq!(Right.turn_right(), Down);
+        assert_eq!(Up.to_vector::<i8>(), Vector::new(0, -1));
+        assert_eq!(Orientation::Horizontal.perpendicular(), Orientation::Vertical);
+    }
+
+    #[test]
+    fn test_dir8() {
+        for d in Dir8::all() {
+            assert_eq!(d.rotate(8), d);
+            assert_eq!(d.rotate(-1).rotate(1), d);
+            assert_eq!(d.reverse().to_vector::<i32>(), -d.to_vector());
+            assert_eq!(Dir::try_from(d).is_err(), d.is_diagonal());
+        }
+        for d in Dir::all() {
+            assert_eq!(Dir::try_from(Dir8::from(d)), Ok(d));
+            assert_eq!(Dir8::from(d).to_vector::<i32>(), d.to_vector());
+        }
+        assert_eq!(Dir8::North.rotate(1), Dir8::NorthEast);
+        assert_eq!(Dir8::East.rotate(-2), Dir8::North);
+        assert_eq!(Dir8::SouthWest.to_vector::<i32>(), Vector::new(-1, 1));
+        assert_eq!(Dir::try_from(Dir8::NorthWest), Err(Dir8::NorthWest));
+    }
+
+    #[test]
+    fn test_ray() {
+        let it: Ray<u8> = Ray::new(Point::new(2, 1), Dir8::SouthWest, 3);
+        assert_eq!(it.collect::<Vec<_>>(), vec![Point::new(2, 1), Point::new(1, 2), Point::new(0, 3)]);
+        // The point beyond the last one would underflow, but is never computed.
+        let it: Ray<u8> = Ray::new(Point::new(1, 1), Dir8::NorthWest, 2);
+        assert_eq!(it.collect::<Vec<_>>(), vec![Point::new(1, 1), Point::new(0, 0)]);
+        assert_eq!(Ray::new(Point::new(1u8, 1), Dir8::North, 0).count(), 0);
+    }
+
+    #[test]
+    fn test_parse_dir() {
+        assert_eq!("R".parse::<Dir>(), Ok(Right));
+        assert_eq!("^".parse::<Dir>(), Ok(Up));
+        assert_eq!(Dir::try_from('S'), Ok(Down));
+        assert_eq!(Dir::try_from('2'), Ok(Left));
+        assert!("RD".parse::<Dir>().is_err());
+        assert!("".parse::<Dir>().is_err());
+        assert_eq!(Dir::try_from('x').unwrap_err().to_string(), "Unknown direction: \"x\"");
+
+        // Encodings only accept their own characters.
+        assert_eq!(DirEncoding::Digits.parse('3'), Ok(Up));
+        assert!(DirEncoding::Digits.parse('U').is_err());
+        for e in DirEncoding::all() {
+            for d in Dir::all() {
+                assert_eq!(e.parse(e.encode(d)), Ok(d));
+            }
+        }
+        assert_eq!(format!("{}{}", Left, Down), "LD");
+    }
+
+    #[test]
+    fn test_rect() {
+        let r: Rect<i32> = Rect::from_corners(Point::new(3, -1), Point::new(1, 2));
+        assert_eq!((r.top_left(), r.bottom_right()), (Point::new(1, -1), Point::new(3, 2)));
+        assert_eq!((r.top_right(), r.bottom_left()), (Point::new(3, -1), Point::new(1, 2)));
+        assert_eq!((r.width(), r.height(), r.area()), (3, 4, 12));
+        assert!(r.contains(&Point::new(3, 0)));
+        assert!(!r.contains(&Point::new(4, 0)));
+        assert_eq!(r.points().count(), 12);
+        assert!(r.points().all(|p| r.contains(&p)));
+
+        let s = Rect::from_corners(Point::new(3, 2), Point::new(5, 5));
+        assert_eq!(r.intersection(&s), Some(Rect::from_corners(Point::new(3, 2), Point::new(3, 2))));
+        assert_eq!(r.union(&s), Rect::from_corners(Point::new(1, -1), Point::new(5, 5)));
+        assert_eq!(r.intersection(&Rect::from_corners(Point::new(4, 0), Point::new(5, 0))), None);
+
+        let points = [Point::new(2, 7), Point::new(-1, 3), Point::new(0, 9)];
+        assert_eq!(Rect::bounding(&points), Some(Rect::from_corners(Point::new(-1, 3), Point::new(2, 9))));
+        assert_eq!(Rect::<i32>::bounding(&[]), None);
+    }
+
+    #[test]
+    fn test_move_by() {
+        let p: Point<usize> = Point::new(0, 2);
+        assert_eq!(p.move_by(0, Up), p);
+        assert_eq!(p.move_by(3, Down), Point::new(0, 5));
+        assert_eq!(p.checked_move_by(2, Up), Some(Point::new(0, 0)));
+        assert_eq!(p.checked_move_by(3, Up), None);
+        assert_eq!(p.checked_move_by(1, Left), None);
+        assert_eq!(p.saturating_move_by(5, Up), Point::new(0, 0));
+        assert_eq!(p.saturating_move_by(usize::MAX, Right), Point::new(usize::MAX, 2));
+        assert_eq!(p.wrapping_move_by(1, Left), Point::new(usize::MAX, 2));
+
+        let q: Point<u8> = Point::new(250, 0);
+        assert_eq!(q.wrapping_move_by(10, Right), Point::new(4, 0));
+    }
+
+    #[test]
+    fn test_traversal() {
+        let r: Rect<u8> = Rect::from_corners(Point::new(0, 0), Point::new(2, 2));
+        let order = |t| r.traverse(t).map(|p| p.x + 3 * p.y).collect::<Vec<_>>();
+        // 0 1 2
+        // 3 4 5
+        // 6 7 8
+        assert_eq!(order(Traversal::Raster), vec![0, 1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(order(Traversal::ColumnMajor), vec![0, 3, 6, 1, 4, 7, 2, 5, 8]);
+        assert_eq!(order(Traversal::Snake), vec![0, 1, 2, 5, 4, 3, 6, 7, 8]);
+        assert_eq!(order(Traversal::Spiral), vec![0, 1, 2, 5, 8, 7, 6, 3, 4]);
+        assert_eq!(order(Traversal::Diagonal), vec![0, 1, 3, 2, 4, 6, 5, 7, 8]);
+        assert!(r.traverse(Traversal::Raster).eq(r.points()));
+    }
+
+    #[test]
+    fn test_traversal_non_square() {
+        let r: Rect<i32> = Rect::from_corners(Point::new(1, 1), Point::new(4, 2));
+        let order = |t| r.traverse(t).map(|p| (p.x, p.y)).collect::<Vec<_>>();
+        assert_eq!(order(Traversal::Spiral), vec![(1, 1), (2, 1), (3, 1), (4, 1), (4, 2), (3, 2), (2, 2), (1, 2)]);
+        assert_eq!(order(Traversal::Diagonal), vec![(1, 1), (2, 1), (1, 2), (3, 1), (2, 2), (4, 1), (3, 2), (4, 2)]);
+
+        // Single rows and columns, and visiting the largest representable values.
+        let row: Rect<u8> = Rect::from_corners(Point::new(253, 255), Point::new(255, 255));
+        let col: Rect<u8> = Rect::from_corners(Point::new(255, 253), Point::new(255, 255));
+        for t in [Traversal::Raster, Traversal::ColumnMajor, Traversal::Snake, Traversal::Spiral, Traversal::Diagonal] {
+            assert_eq!(row.traverse(t).count(), 3);
+            assert_eq!(col.traverse(t).count(), 3);
+        }
+    }
+
+    #[test]
+    fn test_piter_double_ended() {
+        let mut it: Piter<u8> = Piter::new(Point::new(254, 0), Right, 2, Down, 3);
+        assert_eq!(it.len(), 6);
+        assert_eq!(it.next_back(), Some(Point::new(255, 2)));
+        assert_eq!(it.nth(1), Some(Point::new(255, 0)));
+        assert_eq!(it.len(), 3);
+        assert_eq!(it.rev().collect::<Vec<_>>(), vec![Point::new(254, 2), Point::new(255, 1), Point::new(254, 1)]);
+
+        // Iterating to the maximum value cannot overflow.
+        let mut it: Piter<u8> = Piter::new(Point::new(0, 255), Right, 255, Up, 1);
+        assert_eq!(it.len(), 255);
+        assert_eq!(it.next_back(), Some(Point::new(254, 255)));
+        let mut it: Piter<u8> = Piter::new(Point::new(255, 0), Left, 255, Down, 1);
+        assert_eq!(it.next_back(), Some(Point::new(1, 0)));
+    }
+}
@@ -0,0 +1,276 @@
+use num_traits::{PrimInt, WrappingAdd, WrappingSub};
+
+use Dir3::{MinusX, MinusY, MinusZ, PlusX, PlusY, PlusZ};
+
+use crate::xy::abs_diff;
+
+/// One of the three axes in X/Y/Z space.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+/// An orthogonal direction in X/Y/Z space.
+///
+/// Unlike the 2D `Dir`, there is no single conventional "up" in puzzle inputs, so directions are
+/// named for the axis and sign of the coordinate they change.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Dir3 {
+    PlusX,
+    MinusX,
+    PlusY,
+    MinusY,
+    PlusZ,
+    MinusZ,
+}
+
+#[allow(unused)]
+impl Dir3 {
+    pub fn all() -> [Dir3; 6] {
+        [PlusX, MinusX, PlusY, MinusY, PlusZ, MinusZ]
+    }
+
+    pub fn from_axis(axis: Axis, positive: bool) -> Dir3 {
+        match (axis, positive) {
+            (Axis::X, true) => PlusX,
+            (Axis::X, false) => MinusX,
+            (Axis::Y, true) => PlusY,
+            (Axis::Y, false) => MinusY,
+            (Axis::Z, true) => PlusZ,
+            (Axis::Z, false) => MinusZ,
+        }
+    }
+
+    pub fn axis(&self) -> Axis {
+        match self {
+            PlusX | MinusX => Axis::X,
+            PlusY | MinusY => Axis::Y,
+            PlusZ | MinusZ => Axis::Z,
+        }
+    }
+
+    /// Returns whether this direction increases its coordinate.
+    pub fn is_positive(&self) -> bool {
+        matches!(self, PlusX | PlusY | PlusZ)
+    }
+
+    /// Returns the opposite direction.
+    pub fn reverse(&self) -> Dir3 {
+        Dir3::from_axis(self.axis(), !self.is_positive())
+    }
+}
+
+/// An absolute point in X/Y/Z space.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Point3<I: PrimInt> {
+    pub x: I,
+    pub y: I,
+    pub z: I,
+}
+
+#[allow(unused)]
+impl<I: PrimInt> Point3<I> {
+    pub fn new(x: I, y: I, z: I) -> Self { Point3 { x, y, z } }
+
+    pub fn origin() -> Self {
+        Point3 { x: I::zero(), y: I::zero(), z: I::zero() }
+    }
+
+    pub fn get(&self, axis: Axis) -> I {
+        match axis {
+            Axis::X => self.x,
+            Axis::Y => self.y,
+            Axis::Z => self.z,
+        }
+    }
+
+    pub fn offset_xyz(&self, x: I, y: I, z: I) -> Point3<I> {
+        Point3 { x: self.x + x, y: self.y + y, z: self.z + z }
+    }
+
+    /// Returns the point moved by `len` in the given direction. Moving by zero is a no-op.
+    ///
+    /// As for `xy::Point::move_by()`, this panics on overflow in debug builds (and wraps in release
+    /// builds), so for values near the type's limits use one of the explicit variants below.
+    pub fn move_by(&self, len: I, dir: Dir3) -> Point3<I> {
+        self.map_axis(dir, |v| if dir.is_positive() { v + len } else { v - len })
+    }
+
+    /// Returns the point moved by `len` in the given direction, or `None` if the result cannot be
+    /// represented.
+    pub fn checked_move_by(&self, len: I, dir: Dir3) -> Option<Point3<I>> {
+        let v = self.get(dir.axis());
+        let v = if dir.is_positive() { v.checked_add(&len)? } else { v.checked_sub(&len)? };
+        Some(self.map_axis(dir, |_| v))
+    }
+
+    /// Returns the point moved by `len` in the given direction, clamped to the limits of the type.
+    pub fn saturating_move_by(&self, len: I, dir: Dir3) -> Point3<I> {
+        self.map_axis(dir, |v| if dir.is_positive() { v.saturating_add(len) } else { v.saturating_sub(len) })
+    }
+
+    // Returns the point with the coordinate on the direction's axis replaced.
+    fn map_axis<F: FnOnce(I) -> I>(&self, dir: Dir3, f: F) -> Point3<I> {
+        let mut p = *self;
+        let v = match dir.axis() {
+            Axis::X => &mut p.x,
+            Axis::Y => &mut p.y,
+            Axis::Z => &mut p.z,
+        };
+        *v = f(*v);
+        p
+    }
+
+    /// Returns the Manhattan distance to another point.
+    pub fn manhattan(&self, other: &Point3<I>) -> I {
+        abs_diff(self.x, other.x) + abs_diff(self.y, other.y) + abs_diff(self.z, other.z)
+    }
+}
+
+#[allow(unused)]
+impl<I: PrimInt + WrappingAdd + WrappingSub> Point3<I> {
+    /// Returns the point moved by `len` in the given direction, wrapping around at the limits of
+    /// the type.
+    pub fn wrapping_move_by(&self, len: I, dir: Dir3) -> Point3<I> {
+        self.map_axis(dir, |v| if dir.is_positive() { v.wrapping_add(&len) } else { v.wrapping_sub(&len) })
+    }
+}
+
+impl<I: PrimInt> From<(I, I, I)> for Point3<I> {
+    fn from(xyz: (I, I, I)) -> Self {
+        Point3 { x: xyz.0, y: xyz.1, z: xyz.2 }
+    }
+}
+
+/// An axis-aligned box in X/Y/Z space, covering all points `p` where `min <= p <= max` on every
+/// axis (i.e. both corners are inclusive, so a box is never empty).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Box3<I: PrimInt> {
+    min: Point3<I>,
+    max: Point3<I>,
+}
+
+#[allow(unused)]
+impl<I: PrimInt> Box3<I> {
+    /// Creates a box from any two opposite corners.
+    pub fn from_corners(a: Point3<I>, b: Point3<I>) -> Box3<I> {
+        Box3 {
+            min: Point3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            max: Point3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        }
+    }
+
+    pub fn min(&self) -> Point3<I> { self.min }
+
+    pub fn max(&self) -> Point3<I> { self.max }
+
+    /// Returns the number of distinct coordinates covered along the given axis (at least 1).
+    pub fn len(&self, axis: Axis) -> I {
+        self.max.get(axis) - self.min.get(axis) + I::one()
+    }
+
+    /// Returns the number of points in the box.
+    pub fn volume(&self) -> I {
+        self.len(Axis::X) * self.len(Axis::Y) * self.len(Axis::Z)
+    }
+
+    pub fn contains(&self, p: &Point3<I>) -> bool {
+        [Axis::X, Axis::Y, Axis::Z].iter()
+            .all(|&a| self.min.get(a) <= p.get(a) && p.get(a) <= self.max.get(a))
+    }
+
+    /// Returns whether the two boxes have any points in common.
+    pub fn overlaps(&self, other: &Box3<I>) -> bool {
+        self.intersection(other).is_some()
+    }
+
+    /// Returns the box of points common to both boxes, if any.
+    pub fn intersection(&self, other: &Box3<I>) -> Option<Box3<I>> {
+        let min = Point3::new(self.min.x.max(other.min.x), self.min.y.max(other.min.y), self.min.z.max(other.min.z));
+        let max = Point3::new(self.max.x.min(other.max.x), self.max.y.min(other.max.y), self.max.z.min(other.max.z));
+        if min.x <= max.x && min.y <= max.y && min.z <= max.z { Some(Box3 { min, max }) } else { None }
+    }
+
+    /// Returns the box moved by `len` in the given direction (e.g. for dropping a brick).
+    pub fn move_by(&self, len: I, dir: Dir3) -> Box3<I> {
+        Box3 { min: self.min.move_by(len, dir), max: self.max.move_by(len, dir) }
+    }
+
+    /// Returns all points in the box, varying X fastest and Z slowest.
+    pub fn points(&self) -> P3iter<I> {
+        P3iter { bounds: *self, next: Some(self.min) }
+    }
+}
+
+/// An iterator over the points of a `Box3`, analogous to `xy::Piter`.
+#[derive(Debug)]
+pub struct P3iter<I: PrimInt> {
+    bounds: Box3<I>,
+    next: Option<Point3<I>>,
+}
+
+impl<I: PrimInt> Iterator for P3iter<I> {
+    type Item = Point3<I>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let p = self.next?;
+        let (min, max) = (self.bounds.min, self.bounds.max);
+        // Never step beyond the max value, since it might not be representable.
+        self.next = if p.x < max.x {
+            Some(Point3 { x: p.x + I::one(), ..p })
+        } else if p.y < max.y {
+            Some(Point3 { x: min.x, y: p.y + I::one(), ..p })
+        } else if p.z < max.z {
+            Some(Point3 { x: min.x, y: min.y, z: p.z + I::one() })
+        } else {
+            None
+        };
+        Some(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+
+    #[test]
+    fn test_point3() {
+        let p: Point3<u32> = Point3::new(1, 2, 3);
+        assert_eq!(p.move_by(2, MinusZ), Point3::new(1, 2, 1));
+        assert_eq!(p.move_by(0, PlusX), p);
+        assert_eq!(p.checked_move_by(2, MinusX), None);
+        assert_eq!(p.saturating_move_by(2, MinusX), Point3::new(0, 2, 3));
+        assert_eq!(p.wrapping_move_by(2, MinusX), Point3::new(u32::MAX, 2, 3));
+        assert_eq!(p.manhattan(&Point3::new(4, 0, 3)), 5);
+        for d in Dir3::all() {
+            assert_eq!(d.reverse().reverse(), d);
+            assert_eq!(d.reverse().axis(), d.axis());
+            assert_eq!(p.move_by(1, d).move_by(1, d.reverse()), p);
+        }
+    }
+
+    #[test]
+    fn test_box3() {
+        let a: Box3<u32> = Box3::from_corners(Point3::new(2, 0, 5), Point3::new(0, 1, 5));
+        assert_eq!((a.len(Axis::X), a.len(Axis::Y), a.len(Axis::Z)), (3, 2, 1));
+        assert_eq!(a.volume(), 6);
+        assert_eq!(a.points().count(), 6);
+        assert!(a.points().all(|p| a.contains(&p)));
+        assert_eq!(a.points().nth(3), Some(Point3::new(0, 1, 5)));
+
+        let b = Box3::from_corners(Point3::new(1, 1, 7), Point3::new(1, 1, 9));
+        assert!(!a.overlaps(&b));
+        let dropped = b.move_by(2, MinusZ);
+        assert!(a.overlaps(&dropped));
+        assert_eq!(a.intersection(&dropped), Some(Box3::from_corners(Point3::new(1, 1, 5), Point3::new(1, 1, 5))));
+    }
+
+    #[test]
+    fn test_p3iter_at_max() {
+        let b: Box3<u8> = Box3::from_corners(Point3::new(254, 255, 255), Point3::new(255, 255, 255));
+        assert_eq!(b.points().count(), 2);
+    }
+}